@@ -1,14 +1,19 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::configure().build_server(false).compile(
-        &[
-            "proto/auth.proto",
-            "proto/kv.proto",
-            "proto/rpc.proto",
-            "proto/v3lock.proto",
-            "proto/v3election.proto",
-        ],
-        &["proto"],
-    )?;
+    // servers are only needed by the mock server for testing
+    let build_server = std::env::var_os("CARGO_FEATURE_MOCK_SERVER").is_some();
+
+    tonic_build::configure()
+        .build_server(build_server)
+        .compile(
+            &[
+                "proto/auth.proto",
+                "proto/kv.proto",
+                "proto/rpc.proto",
+                "proto/v3lock.proto",
+                "proto/v3election.proto",
+            ],
+            &["proto"],
+        )?;
 
     Ok(())
 }
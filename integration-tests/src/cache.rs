@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use tokio::time::timeout;
+
+use etcd_rs::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn connect(srv: &MockEtcdServer) -> Client {
+    Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect")
+}
+
+#[tokio::test]
+async fn test_cached_kv_hit() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+    let revision = cli
+        .put(("a/foo", "bar"))
+        .await
+        .expect("put kv")
+        .header
+        .revision();
+
+    let cache = CachedKv::start(cli.clone(), KeyRange::prefix("a/"))
+        .await
+        .expect("start cache");
+    let requests = srv.requests("range");
+
+    for _ in 0..3 {
+        let resp = cache.get("a/foo").await.expect("get kv");
+        assert_eq!("bar", resp.kvs[0].value_str());
+        assert!(resp.header.revision() >= revision);
+    }
+    // missing keys are cached as well
+    for _ in 0..3 {
+        let resp = cache.get("a/missing").await.expect("get kv");
+        assert!(resp.kvs.is_empty());
+        assert_eq!(resp.count, 0);
+    }
+    assert_eq!(srv.requests("range"), requests + 2);
+    assert_eq!(cache.len(), 2);
+
+    // reads outside the keyspace or with options are not cached
+    cli.put(("b", "b")).await.expect("put kv");
+    cache.get("b").await.expect("get kv");
+    cache.get("b").await.expect("get kv");
+    cache
+        .get(RangeRequest::new(KeyRange::key("a/foo")).revision(revision))
+        .await
+        .expect("get kv at revision");
+    assert_eq!(srv.requests("range"), requests + 5);
+}
+
+#[tokio::test]
+async fn test_cached_kv_watch_updates() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+    cli.put(("a/foo", "bar")).await.expect("put kv");
+
+    let cache = CachedKv::start(cli.clone(), KeyRange::prefix("a/"))
+        .await
+        .expect("start cache");
+    cache.get("a/foo").await.expect("get kv");
+    let requests = srv.requests("range");
+
+    let revision = srv
+        .fake_client()
+        .put(("a/foo", "baz"))
+        .await
+        .expect("put kv")
+        .header
+        .revision();
+    timeout(TIMEOUT, async {
+        while cache.watch_revision() < revision {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("watch caught up");
+
+    let resp = cache.get("a/foo").await.expect("get kv");
+    assert_eq!("baz", resp.kvs[0].value_str());
+    assert_eq!(resp.header.revision(), revision);
+
+    srv.fake_client().delete("a/foo").await.expect("delete kv");
+    timeout(TIMEOUT, async {
+        while !cache.get("a/foo").await.expect("get kv").kvs.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("deleted key invalidated");
+    assert_eq!(srv.requests("range"), requests);
+}
+
+#[tokio::test]
+async fn test_cached_kv_reads_own_writes() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+    cli.put(("a/foo", "bar")).await.expect("put kv");
+
+    let cache = CachedKv::start(cli, KeyRange::prefix("a/"))
+        .await
+        .expect("start cache");
+    cache.get("a/foo").await.expect("get kv");
+
+    for i in 0..10 {
+        let value = i.to_string();
+        let revision = cache
+            .put(("a/foo", value.as_str()))
+            .await
+            .expect("put kv")
+            .header
+            .revision();
+        let resp = cache.get("a/foo").await.expect("get kv");
+        assert_eq!(value, resp.kvs[0].value_str());
+        assert!(resp.header.revision() >= revision);
+    }
+}
+
+#[tokio::test]
+async fn test_cached_kv_bounds() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+
+    let cache = CachedKv::start(cli, KeyRange::prefix("a/"))
+        .await
+        .expect("start cache")
+        .max_entries(1)
+        .ttl(Duration::from_millis(50));
+    let requests = srv.requests("range");
+
+    cache.get("a/1").await.expect("get kv");
+    cache.get("a/2").await.expect("get kv");
+    assert_eq!(cache.len(), 1);
+    // the least recently used key was dropped
+    cache.get("a/1").await.expect("get kv");
+    assert_eq!(srv.requests("range"), requests + 3);
+
+    cache.get("a/1").await.expect("get kv");
+    assert_eq!(srv.requests("range"), requests + 3);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    cache.get("a/1").await.expect("get kv");
+    assert_eq!(srv.requests("range"), requests + 4);
+}
+
+#[tokio::test]
+async fn test_cached_kv_lru() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+
+    let cache = CachedKv::start(cli, KeyRange::prefix("a/"))
+        .await
+        .expect("start cache")
+        .max_entries(2);
+    let requests = srv.requests("range");
+
+    cache.get("a/1").await.expect("get kv");
+    cache.get("a/2").await.expect("get kv");
+    // a hit makes the key the most recently used, so the other key is dropped
+    cache.get("a/1").await.expect("get kv");
+    cache.get("a/3").await.expect("get kv");
+    assert_eq!(cache.len(), 2);
+    assert_eq!(srv.requests("range"), requests + 3);
+
+    cache.get("a/1").await.expect("get kv");
+    assert_eq!(srv.requests("range"), requests + 3);
+    cache.get("a/2").await.expect("get kv");
+    assert_eq!(srv.requests("range"), requests + 4);
+}
+
+#[tokio::test]
+async fn test_cached_kv_dropped_on_compaction() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+    let addr = srv1
+        .url()
+        .trim_start_matches("http://")
+        .parse()
+        .expect("address of mock server");
+
+    let cli = connect(&srv1).await;
+    cli.put(("a/foo", "bar")).await.expect("put kv");
+    let cache = CachedKv::start(cli, KeyRange::prefix("a/"))
+        .await
+        .expect("start cache");
+    cache.get("a/foo").await.expect("get kv");
+
+    // the watch misses the change while its member is down, and it is compacted
+    srv1.shutdown().await;
+    let writer = srv2.fake_client();
+    let revision = writer
+        .put(("a/foo", "baz"))
+        .await
+        .expect("put kv")
+        .header
+        .revision();
+    writer.put(("b", "b")).await.expect("put kv");
+    writer.compact(revision + 1).await.expect("compact");
+
+    let srv1 = srv2.replica_at(addr).await.expect("restart mock server");
+    timeout(TIMEOUT, async {
+        while cache.watch_revision() <= revision {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("watch resumed");
+
+    let requests = srv1.requests("range");
+    for _ in 0..2 {
+        let resp = cache.get("a/foo").await.expect("get kv");
+        assert_eq!("baz", resp.kvs[0].value_str());
+    }
+    assert_eq!(srv1.requests("range"), requests + 1);
+}
+
+#[tokio::test]
+async fn test_cached_kv_lagging_member() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(
+        ClientConfig::new([srv.endpoint()]).read_consistency(ReadConsistency::Serializable),
+    )
+    .await
+    .expect("connect");
+    cli.put(("a/foo", "bar")).await.expect("put kv");
+    cli.put(("a/bar", "bar")).await.expect("put kv");
+
+    let cache = CachedKv::start(cli, KeyRange::prefix("a/"))
+        .await
+        .expect("start cache");
+    srv.set_lagging(true);
+
+    // a miss served by the lagging member is behind a write through the cache
+    let revision = cache
+        .put(("a/foo", "baz"))
+        .await
+        .expect("put kv")
+        .header
+        .revision();
+    let resp = cache.get("a/foo").await.expect("get kv");
+    assert_eq!("baz", resp.kvs[0].value_str());
+    assert!(resp.header.revision() >= revision);
+
+    // or behind the watch
+    let revision = srv
+        .fake_client()
+        .put(("a/bar", "baz"))
+        .await
+        .expect("put kv")
+        .header
+        .revision();
+    timeout(TIMEOUT, async {
+        while cache.watch_revision() < revision {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("watch caught up");
+    let resp = cache.get("a/bar").await.expect("get kv");
+    assert_eq!("baz", resp.kvs[0].value_str());
+
+    // the up to date result is cached
+    let requests = srv.requests("range");
+    assert_eq!(
+        "baz",
+        cache.get("a/bar").await.expect("get kv").kvs[0].value_str()
+    );
+    assert_eq!(srv.requests("range"), requests);
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tonic::metadata::MetadataMap;
+use tower::ServiceBuilder;
+
+use etcd_rs::*;
+
+#[tokio::test]
+async fn test_request_timeout() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(
+        ClientConfig::new([srv.endpoint()]).request_timeout(Duration::from_millis(100)),
+    )
+    .await
+    .expect("connect to mock server");
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    srv.set_delay(Duration::from_millis(300));
+    assert!(matches!(cli.get("foo").await, Err(Error::Timeout)));
+    assert!(matches!(
+        cli.watch(KeyRange::key("foo")).await,
+        Err(Error::Timeout)
+    ));
+
+    // per-call options override the default
+    let resp = cli
+        .with_call_options(CallOptions::new().timeout(Duration::from_secs(5)))
+        .get("foo")
+        .await
+        .expect("get kv with longer timeout");
+    assert_eq!("bar", resp.kvs[0].value_str());
+}
+
+#[tokio::test]
+async fn test_call_options_metadata() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+
+    let headers = Arc::new(Mutex::new(vec![]));
+    let layer = {
+        let headers = Arc::clone(&headers);
+        ServiceBuilder::new().map_request(move |req: tonic::codegen::http::Request<_>| {
+            headers.lock().unwrap().push(req.headers().clone());
+            req
+        })
+    };
+
+    let cli = Client::connect_with_layer(ClientConfig::new([srv.endpoint()]), layer)
+        .await
+        .expect("connect to mock server");
+
+    let mut metadata = MetadataMap::new();
+    metadata.insert("x-request-id", "42".parse().unwrap());
+    let opts = CallOptions::new().metadata(metadata).require_leader(true);
+
+    cli.with_call_options(opts)
+        .put(("foo", "bar"))
+        .await
+        .expect("put kv");
+    cli.get("foo").await.expect("get kv");
+    // a per-call option overrides the one of the client
+    cli.with_call_options(CallOptions::new().require_leader(true))
+        .with_call_options(CallOptions::new().require_leader(false))
+        .get("foo")
+        .await
+        .expect("get kv");
+
+    let headers = headers.lock().unwrap();
+    assert_eq!(headers[0].get("x-request-id").unwrap(), "42");
+    assert_eq!(headers[0].get("hasleader").unwrap(), "true");
+    assert!(headers[1].get("x-request-id").is_none());
+    assert!(headers[1].get("hasleader").is_none());
+    assert!(headers[2].get("hasleader").is_none());
+}
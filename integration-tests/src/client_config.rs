@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use etcd_rs::*;
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("etcd-rs-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create config dir");
+    let path = dir.join(name);
+    std::fs::write(&path, content).expect("write config file");
+    path
+}
+
+async fn from_vars(vars: &[(&str, &str)]) -> Result<ClientConfig> {
+    let vars = vars
+        .iter()
+        .map(|(name, value)| (format!("ETCDCTL_{}", name), value.to_string()))
+        .collect::<HashMap<_, _>>();
+    ClientConfig::from_vars(|name| vars.get(name).cloned()).await
+}
+
+#[tokio::test]
+async fn test_from_vars() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let addr = srv.url().trim_start_matches("http://").to_owned();
+
+    let mut vars = vec![
+        ("ENDPOINTS", addr.as_str()),
+        ("DIAL_TIMEOUT", "2s"),
+        ("COMMAND_TIMEOUT", "1m30s"),
+        ("KEEPALIVE_TIME", "500ms"),
+        ("MAX_RECV_BYTES", "1048576"),
+    ];
+    let cfg = from_vars(&vars).await.expect("config from vars");
+
+    assert_eq!(Duration::from_secs(2), cfg.connect_timeout);
+    assert_eq!(Some(Duration::from_secs(90)), cfg.request_timeout);
+    assert_eq!(Duration::from_millis(500), cfg.http2_keep_alive_interval);
+    assert_eq!(1048576, cfg.max_decoding_message_size);
+    assert!(cfg.auth.is_none());
+    assert_eq!(format!("http://{}", addr), cfg.endpoints[0].url());
+
+    let cli = Client::connect(cfg).await.expect("connect to mock server");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    vars.push(("USER", "root:secret"));
+    let cfg = from_vars(&vars).await.expect("config from vars");
+    assert_eq!(Some(("root".to_owned(), "secret".to_owned())), cfg.auth);
+
+    vars.pop();
+    vars.push(("USER", "root"));
+    assert!(matches!(from_vars(&vars).await, Err(Error::Config(_))));
+    vars.push(("PASSWORD", "secret"));
+    let cfg = from_vars(&vars).await.expect("config from vars");
+    assert_eq!(Some(("root".to_owned(), "secret".to_owned())), cfg.auth);
+
+    // empty variables are ignored
+    vars.push(("CACERT", ""));
+    from_vars(&vars).await.expect("config from vars");
+
+    vars.push(("DIAL_TIMEOUT", "2 seconds"));
+    assert!(matches!(from_vars(&vars).await, Err(Error::Config(_))));
+}
+
+#[tokio::test]
+async fn test_from_vars_tls() {
+    let ca = temp_file("ca.pem", "");
+    let ca = ca.to_str().expect("path of ca");
+
+    // CA only TLS reads the CA certificate
+    let vars = [
+        ("ENDPOINTS", "10.0.0.1:2379"),
+        ("CACERT", "/nonexistent/ca.pem"),
+    ];
+    assert!(matches!(from_vars(&vars).await, Err(Error::IOError(_))));
+    let cfg = from_vars(&[("ENDPOINTS", "10.0.0.1:2379"), ("CACERT", ca)])
+        .await
+        .expect("config from vars");
+    assert_eq!("https://10.0.0.1:2379", cfg.endpoints[0].url());
+
+    let vars = [("CACERT", ca), ("CERT", "/nonexistent/cert.pem")];
+    assert!(matches!(from_vars(&vars).await, Err(Error::Config(_))));
+    let vars = [
+        ("CACERT", ca),
+        ("CERT", "/nonexistent/cert.pem"),
+        ("KEY", "/nonexistent/key.pem"),
+    ];
+    assert!(matches!(from_vars(&vars).await, Err(Error::IOError(_))));
+
+    // the server is verified with the system roots without the CA certificate
+    let vars = [("ENDPOINTS", "10.0.0.1:2379"), ("CERT", ca), ("KEY", ca)];
+    let cfg = from_vars(&vars).await.expect("config from vars");
+    assert_eq!("https://10.0.0.1:2379", cfg.endpoints[0].url());
+
+    // the certificates are read for discovered endpoints as well
+    let vars = [
+        ("DISCOVERY_SRV", "example.com"),
+        ("CACERT", "/nonexistent/ca.pem"),
+    ];
+    assert!(matches!(from_vars(&vars).await, Err(Error::IOError(_))));
+    let cfg = from_vars(&[("DISCOVERY_SRV", "example.com"), ("CACERT", ca)])
+        .await
+        .expect("config from vars");
+    assert_eq!(
+        "example.com",
+        cfg.discovery.expect("discovery of endpoints").domain()
+    );
+}
+
+#[tokio::test]
+async fn test_from_file() {
+    let path = temp_file(
+        "etcd.toml",
+        r#"
+endpoints = ["10.0.0.1:2379", "http://10.0.0.2:2379"]
+user = "root"
+password = "secret"
+dial-timeout = "3s"
+keepalive-timeout = "10s"
+max-request-bytes = 4096
+"#,
+    );
+    let cfg = ClientConfig::from_file(&path).await.expect("load toml");
+    assert_eq!(
+        vec!["http://10.0.0.1:2379", "http://10.0.0.2:2379"],
+        cfg.endpoints.iter().map(|e| e.url()).collect::<Vec<_>>()
+    );
+    assert_eq!(Some(("root".to_owned(), "secret".to_owned())), cfg.auth);
+    assert_eq!(Duration::from_secs(3), cfg.connect_timeout);
+    assert_eq!(Duration::from_secs(10), cfg.http2_keep_alive_timeout);
+    assert_eq!(4096, cfg.max_request_bytes);
+
+    let path = temp_file(
+        "etcd.yaml",
+        "endpoints:\n  - 10.0.0.3:2379\ncommand-timeout: 5s\n",
+    );
+    let cfg = ClientConfig::from_file(&path).await.expect("load yaml");
+    assert_eq!("http://10.0.0.3:2379", cfg.endpoints[0].url());
+    assert_eq!(Some(Duration::from_secs(5)), cfg.request_timeout);
+
+    // unknown keys are rejected rather than ignored
+    let path = temp_file("invalid.yml", "endpoint: 10.0.0.3:2379\n");
+    assert!(matches!(
+        ClientConfig::from_file(&path).await,
+        Err(Error::Config(_))
+    ));
+
+    let path = temp_file("etcd.json", "{}");
+    assert!(matches!(
+        ClientConfig::from_file(&path).await,
+        Err(Error::Config(_))
+    ));
+}
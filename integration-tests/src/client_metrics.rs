@@ -0,0 +1,118 @@
+use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+use metrics_util::MetricKind;
+
+use etcd_rs::*;
+
+fn value(
+    snapshotter: &Snapshotter,
+    kind: MetricKind,
+    name: &str,
+    labels: &[(&str, &str)],
+) -> Option<DebugValue> {
+    snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .find(|(key, _, _, _)| {
+            key.kind() == kind
+                && key.key().name() == name
+                && labels.iter().all(|(k, v)| {
+                    key.key()
+                        .labels()
+                        .any(|label| label.key() == *k && label.value() == *v)
+                })
+        })
+        .map(|(_, _, _, value)| value)
+}
+
+fn counter(snapshotter: &Snapshotter, name: &str, labels: &[(&str, &str)]) -> u64 {
+    match value(snapshotter, MetricKind::Counter, name, labels) {
+        Some(DebugValue::Counter(v)) => v,
+        _ => 0,
+    }
+}
+
+fn gauge(snapshotter: &Snapshotter, name: &str) -> f64 {
+    match value(snapshotter, MetricKind::Gauge, name, &[]) {
+        Some(DebugValue::Gauge(v)) => v.into_inner(),
+        _ => 0.0,
+    }
+}
+
+#[test]
+fn test_client_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    // the recorder is installed on the current thread, so is the runtime
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    metrics::with_local_recorder(&recorder, || {
+        rt.block_on(async {
+            let srv = MockEtcdServer::start().await.expect("start mock server");
+            let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+                .await
+                .expect("connect to mock server");
+
+            cli.put(("foo", "bar")).await.expect("put kv");
+            srv.fail_next(tonic::Status::unavailable("injected"));
+            cli.get("foo").await.unwrap_err();
+
+            let (stream, _) = cli.watch(KeyRange::key("foo")).await.expect("watch");
+            let lease = cli
+                .grant_lease(std::time::Duration::from_secs(10))
+                .await
+                .unwrap();
+            let keep_alive = cli.keep_alive_for(lease.id).await.expect("keep alive");
+
+            assert_eq!(
+                counter(
+                    &snapshotter,
+                    "etcd_client_requests_total",
+                    &[("method", "put")]
+                ),
+                1
+            );
+            assert_eq!(
+                counter(
+                    &snapshotter,
+                    "etcd_client_errors_total",
+                    &[("method", "range"), ("code", "Unavailable")]
+                ),
+                1
+            );
+            assert!(
+                counter(
+                    &snapshotter,
+                    "etcd_client_sent_bytes_total",
+                    &[("method", "put")]
+                ) > 0
+            );
+            assert!(
+                counter(
+                    &snapshotter,
+                    "etcd_client_received_bytes_total",
+                    &[("method", "put")]
+                ) > 0
+            );
+            assert!(value(
+                &snapshotter,
+                MetricKind::Histogram,
+                "etcd_client_request_duration_seconds",
+                &[("method", "put")]
+            )
+            .is_some());
+
+            assert_eq!(gauge(&snapshotter, "etcd_client_active_watches"), 1.0);
+            assert_eq!(gauge(&snapshotter, "etcd_client_keep_alive_leases"), 1.0);
+
+            drop(stream);
+            drop(keep_alive);
+            assert_eq!(gauge(&snapshotter, "etcd_client_active_watches"), 0.0);
+            assert_eq!(gauge(&snapshotter, "etcd_client_keep_alive_leases"), 0.0);
+        })
+    });
+}
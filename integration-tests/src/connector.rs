@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::net::TcpStream;
+use tonic::codegen::http::Uri;
+
+use etcd_rs::*;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_endpoint() {
+    let dir = std::env::temp_dir().join(format!("etcd-rs-unix-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create socket dir");
+    let path = dir.join("etcd.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let srv = MockEtcdServer::bind_unix(&path)
+        .await
+        .expect("start mock server");
+    assert_eq!(format!("unix://{}", path.display()), srv.url());
+
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect to unix socket");
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    drop(cli);
+    srv.shutdown().await;
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_custom_connector() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    // dials the address of the server instead of the URL, like a proxy would
+    let dialed = Arc::new(AtomicUsize::new(0));
+    let endpoint = |srv: &MockEtcdServer| {
+        let addr = srv.url().trim_start_matches("http://").to_owned();
+        let dialed = Arc::clone(&dialed);
+        Endpoint::new("http://etcd.invalid:2379").connector(tower::service_fn(move |uri: Uri| {
+            assert_eq!(Some("etcd.invalid"), uri.host());
+            dialed.fetch_add(1, Ordering::SeqCst);
+            TcpStream::connect(addr.clone())
+        }))
+    };
+
+    let cli = Client::connect(ClientConfig::new([endpoint(&srv1), endpoint(&srv2)]))
+        .await
+        .expect("connect through connector");
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    // requests are spread across both endpoints
+    assert_eq!(2, dialed.load(Ordering::SeqCst));
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use etcd_rs::*;
+
+/// Resolves SRV records from a table which can be changed by tests.
+#[derive(Clone, Default)]
+struct StubResolver {
+    records: Arc<Mutex<HashMap<String, Vec<SrvRecord>>>>,
+}
+
+impl StubResolver {
+    fn set(&self, name: &str, servers: &[&MockEtcdServer]) {
+        let records = servers
+            .iter()
+            .map(|srv| {
+                let (host, port) = srv
+                    .url()
+                    .trim_start_matches("http://")
+                    .rsplit_once(':')
+                    .unwrap();
+                SrvRecord::new(host, port.parse().unwrap())
+            })
+            .collect();
+        self.records
+            .lock()
+            .unwrap()
+            .insert(name.to_owned(), records);
+    }
+}
+
+#[async_trait]
+impl SrvResolver for StubResolver {
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>> {
+        Ok(self
+            .records
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[tokio::test]
+async fn test_srv_discovery() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let resolver = StubResolver::default();
+    resolver.set("_etcd-client._tcp.example.com", &[&srv1]);
+
+    let discovery = SrvDiscovery::with_resolver("example.com", resolver.clone())
+        .refresh_interval(Duration::from_millis(50));
+    let endpoints = discovery.resolve().await.expect("resolve endpoints");
+    assert_eq!(
+        vec![srv1.url()],
+        endpoints.iter().map(|e| e.url()).collect::<Vec<_>>()
+    );
+
+    let cli = Client::connect(ClientConfig::new(vec![]).discovery(discovery))
+        .await
+        .expect("connect to discovered endpoints");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    // the client follows the records after they are resolved again
+    resolver.set("_etcd-client._tcp.example.com", &[&srv2]);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    srv1.shutdown().await;
+
+    let resp = cli.get("foo").await.expect("get kv from new endpoint");
+    assert_eq!("bar", resp.kvs[0].value_str());
+}
+
+#[tokio::test]
+async fn test_srv_discovery_errors() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let resolver = StubResolver::default();
+
+    let discovery = SrvDiscovery::with_resolver("example.com", resolver.clone());
+    assert!(matches!(
+        discovery.resolve().await,
+        Err(Error::Discovery(_))
+    ));
+
+    // TLS must be configured for the records of `_etcd-client-ssl._tcp`
+    resolver.set("_etcd-client-ssl-infra._tcp.example.com", &[&srv]);
+    assert!(discovery.resolve().await.is_err());
+    let discovery = discovery.service_name("infra");
+    assert!(matches!(
+        discovery.resolve().await,
+        Err(Error::Discovery(_))
+    ));
+
+    let discovery = discovery.tls_raw("ca", "cert", "key");
+    let endpoints = discovery.resolve().await.expect("resolve endpoints");
+    assert_eq!(srv.url().replace("http://", "https://"), endpoints[0].url());
+}
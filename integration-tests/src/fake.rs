@@ -0,0 +1,255 @@
+use std::time::Duration;
+
+use tonic::Code;
+
+use etcd_rs::*;
+
+use crate::support::KVOp;
+
+#[tokio::test]
+async fn test_fake_kv_revisions() {
+    let cli = FakeClient::new();
+
+    let resp = cli.put(("foo", "bar1")).await.expect("put kv");
+    assert_eq!(resp.header.revision(), 2);
+    cli.put(("foo", "bar2")).await.expect("put kv");
+    cli.put(("fop", "baz")).await.expect("put kv");
+
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(
+        resp.kvs,
+        vec![KeyValue {
+            key: "foo".into(),
+            value: "bar2".into(),
+            create_revision: 2,
+            mod_revision: 3,
+            version: 2,
+            lease: 0
+        }]
+    );
+
+    let resp = cli
+        .get(RangeRequest::new(KeyRange::key("foo")).revision(2))
+        .await
+        .expect("get kv at revision");
+    assert_eq!("bar1", resp.kvs[0].value_str());
+
+    let resp = cli.get_range("foo", "fop").await.expect("get range");
+    assert_eq!(resp.count, 1);
+
+    let resp = cli.get_all().await.expect("get all");
+    assert_eq!(resp.count, 2);
+
+    let resp = cli
+        .delete(DeleteRequest::new(KeyRange::prefix("fo")).prev_kv(true))
+        .await
+        .expect("delete by prefix");
+    assert_eq!(resp.deleted, 2);
+    assert_eq!(resp.header.revision(), 5);
+
+    let resp = cli.put(("foo", "bar3")).await.expect("put kv");
+    assert_eq!(resp.header.revision(), 6);
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(resp.kvs[0].version, 1);
+    assert_eq!(resp.kvs[0].create_revision, 6);
+}
+
+#[tokio::test]
+async fn test_fake_txn() {
+    let cli = FakeClient::new();
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    let mut txn = TxnRequest::new()
+        .when_value(KeyRange::key("foo"), TxnCmp::Equal, "bar")
+        .when_version(KeyRange::key("missing"), TxnCmp::Equal, 0);
+    let put = txn.push_and_then(PutRequest::from(("foo", "baz")).prev_kv(true));
+    let get = txn.push_and_then(RangeRequest::from("foo"));
+
+    let resp = cli.txn(txn).await.expect("txn");
+    assert!(resp.succeeded);
+    assert_eq!(resp.header.revision(), 3);
+    assert_eq!(
+        "bar",
+        resp.get(&put).expect("put result").prev_kv.value_str()
+    );
+    assert_eq!(
+        "baz",
+        resp.get(&get).expect("range result").kvs[0].value_str()
+    );
+
+    let resp = cli
+        .txn(
+            TxnRequest::new()
+                .when_value(KeyRange::key("foo"), TxnCmp::Equal, "bar")
+                .and_then(PutRequest::from(("foo", "qux"))),
+        )
+        .await
+        .expect("txn");
+    assert!(!resp.succeeded);
+    assert_eq!(resp.header.revision(), 3);
+
+    match cli
+        .txn(
+            TxnRequest::new()
+                .and_then(PutRequest::from(("a", "1")))
+                .and_then(PutRequest::from(("a", "2"))),
+        )
+        .await
+    {
+        Err(Error::Response(status)) => assert_eq!(status.code(), Code::InvalidArgument),
+        _ => unreachable!(),
+    }
+
+    // a failed txn writes nothing
+    for txn in [
+        TxnRequest::new()
+            .and_then(PutRequest::from(("a", "1")))
+            .and_then(RangeRequest::new(KeyRange::key("foo")).revision(100)),
+        TxnRequest::new()
+            .and_then(PutRequest::from(("a", "1")))
+            .and_then(DeleteRequest::new(KeyRange::prefix("a"))),
+    ] {
+        assert!(cli.txn(txn).await.is_err());
+    }
+    assert!(cli.get("a").await.expect("get kv").kvs.is_empty());
+    assert_eq!(cli.revision(), 3);
+}
+
+#[tokio::test]
+async fn test_fake_watch() {
+    let cli = FakeClient::new();
+
+    cli.put(("foo1", "bar0")).await.expect("put kv");
+    let start = cli.revision() + 1;
+    cli.put(("foo1", "bar1")).await.expect("put kv");
+
+    let (mut stream, cancel) = cli
+        .watch(WatchCreateRequest::create(KeyRange::prefix("foo")).start_revision(start))
+        .await
+        .expect("watch created");
+
+    let ops = [
+        KVOp::Put("foo1".to_owned(), "bar1".to_owned()),
+        KVOp::Put("foo2".to_owned(), "bar2".to_owned()),
+        KVOp::Delete("foo1".to_owned()),
+    ];
+
+    apply_kv_ops!(cli, ops[1..]);
+    cli.put(("other", "baz")).await.expect("put kv");
+
+    cancel.cancel().await.expect("watch canceled");
+
+    assert_ops_events!(ops, stream);
+}
+
+#[tokio::test]
+async fn test_fake_lease_expiry() {
+    let cli = FakeClient::new();
+
+    let lease = cli
+        .grant_lease(Duration::from_secs(10))
+        .await
+        .expect("grant lease");
+    cli.put(PutRequest::from(("foo", "bar")).lease(lease.id))
+        .await
+        .expect("put kv with lease");
+
+    cli.advance(Duration::from_secs(6));
+    let mut keeper = cli.keep_alive_for(lease.id).await.expect("keep alive");
+    cli.advance(Duration::from_secs(6));
+    let resp = keeper.keep_alive().await.expect("keep alive").unwrap();
+    assert_eq!(resp.ttl, 10);
+
+    let resp = cli.time_to_live(lease.id).await.expect("time to live");
+    assert_eq!(resp.ttl, 10);
+
+    cli.advance(Duration::from_secs(10));
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(resp.count, 0);
+
+    let resp = cli.time_to_live(lease.id).await.expect("time to live");
+    assert_eq!(resp.ttl, -1);
+}
+
+#[tokio::test]
+async fn test_fake_compact() {
+    let cli = FakeClient::new();
+
+    for _ in 0..5 {
+        cli.put(("foo", "bar")).await.expect("put key-value");
+    }
+
+    cli.compact(4).await.expect("compact with current revision");
+    cli.compact(4)
+        .await
+        .expect_err("compact with compacted revision");
+    cli.compact(42)
+        .await
+        .expect_err("compact with future revision");
+
+    match cli
+        .get(RangeRequest::new(KeyRange::key("foo")).revision(3))
+        .await
+    {
+        Err(Error::Response(status)) => assert_eq!(status.code(), Code::OutOfRange),
+        _ => unreachable!(),
+    }
+
+    let resp = cli
+        .get(RangeRequest::new(KeyRange::key("foo")).revision(4))
+        .await
+        .expect("get kv at compacted revision");
+    assert_eq!(resp.kvs[0].version, 3);
+
+    let (mut stream, _cancel) = cli
+        .watch(WatchCreateRequest::create(KeyRange::key("foo")).start_revision(2))
+        .await
+        .expect("watch created");
+    match stream.inbound().await {
+        WatchInbound::Closed => {}
+        others => panic!("should not reach here but got: {:?}", others),
+    }
+}
+
+#[tokio::test]
+async fn test_fake_bulk_writer_limits() {
+    let cli = FakeClient::new().with_request_limits(RequestLimits {
+        max_txn_ops: 8,
+        ..Default::default()
+    });
+
+    // batches follow the limits of the client
+    let mut writer = BulkWriter::new(&cli);
+    for i in 0..20 {
+        writer.put((format!("bulk-{:02}", i), "foo"));
+    }
+    let resp = writer.commit().await.expect("bulk write");
+    assert!(resp.is_success());
+    assert_eq!(resp.batches.len(), 3);
+
+    // nothing is sent without operations
+    let revision = cli.revision();
+    for atomic in [false, true] {
+        let resp = BulkWriter::new(&cli)
+            .atomic(atomic)
+            .commit()
+            .await
+            .expect("empty bulk write");
+        assert!(resp.batches.is_empty());
+    }
+    assert_eq!(cli.revision(), revision);
+
+    // atomic writes leave room for the raft overhead
+    let mut writer = BulkWriter::new(&cli)
+        .limits(RequestLimits {
+            max_txn_ops: 8,
+            max_request_bytes: 2048,
+        })
+        .atomic(true);
+    writer.put(("big", vec![0u8; 1536]));
+    assert!(matches!(
+        writer.commit().await,
+        Err(Error::RequestTooLarge { .. })
+    ));
+}
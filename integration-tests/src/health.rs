@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use etcd_rs::*;
+
+/// Waits until the health of the endpoint matches.
+async fn wait_health(cli: &Client, url: &str, healthy: bool) -> EndpointHealth {
+    for _ in 0..100 {
+        let health = cli
+            .endpoint_health()
+            .into_iter()
+            .find(|h| h.endpoint == url)
+            .expect("endpoint is checked");
+        if health.healthy == healthy && health.checked_at.is_some() {
+            return health;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("health of {} did not become {}", url, healthy);
+}
+
+#[tokio::test]
+async fn test_eject_unhealthy_endpoint() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()])
+        .health_check(HealthCheck::new().interval(Duration::from_millis(50)));
+    let cli = Client::connect(cfg).await.expect("connect");
+    assert_eq!(2, cli.endpoint_health().len());
+    wait_health(&cli, srv1.url(), true).await;
+    wait_health(&cli, srv2.url(), true).await;
+
+    srv2.set_nospace(true);
+    let health = wait_health(&cli, srv2.url(), false).await;
+    assert!(health.error.expect("error of probe").contains("NOSPACE"));
+
+    // writes are not sent to the ejected endpoint
+    for i in 0..10 {
+        cli.put((format!("foo{}", i), "bar")).await.expect("put kv");
+    }
+
+    srv2.set_nospace(false);
+    wait_health(&cli, srv2.url(), true).await;
+
+    srv1.set_no_leader(true);
+    let health = wait_health(&cli, srv1.url(), false).await;
+    assert_eq!(Some("no leader"), health.error.as_deref());
+    srv1.set_no_leader(false);
+    wait_health(&cli, srv1.url(), true).await;
+}
+
+#[tokio::test]
+async fn test_keep_endpoints_when_all_unhealthy() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+
+    let cfg = ClientConfig::new([srv.endpoint()])
+        .health_check(HealthCheck::new().interval(Duration::from_millis(50)));
+    let cli = Client::connect(cfg).await.expect("connect");
+
+    srv.set_nospace(true);
+    wait_health(&cli, srv.url(), false).await;
+
+    // the only endpoint is kept, so reads still succeed
+    cli.get("foo").await.expect("get kv");
+}
+
+#[tokio::test]
+async fn test_serializable_range_probe() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()]).health_check(
+        HealthCheck::new()
+            .interval(Duration::from_millis(50))
+            .timeout(Duration::from_millis(200))
+            .probe(HealthProbe::SerializableRange),
+    );
+    let cli = Client::connect(cfg).await.expect("connect");
+
+    // alarms do not fail serializable reads
+    srv2.set_nospace(true);
+    wait_health(&cli, srv2.url(), true).await;
+
+    srv2.set_unavailable(true);
+    let health = wait_health(&cli, srv2.url(), false).await;
+    assert!(health
+        .error
+        .expect("error of probe")
+        .contains("Unavailable"));
+    for _ in 0..10 {
+        cli.get("foo").await.expect("get kv");
+    }
+
+    srv2.set_unavailable(false);
+    wait_health(&cli, srv2.url(), true).await;
+}
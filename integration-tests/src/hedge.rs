@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use metrics_util::MetricKind;
+
+use etcd_rs::*;
+
+#[tokio::test]
+async fn test_hedge_slow_endpoint() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()])
+        .hedging(Hedging::new().initial_delay(Duration::from_millis(20)));
+    let cli = Client::connect(cfg).await.expect("connect");
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let lease = cli
+        .grant_lease(Duration::from_secs(60))
+        .await
+        .expect("grant lease");
+
+    srv1.set_delay(Duration::from_secs(2));
+    let start = Instant::now();
+    for _ in 0..10 {
+        let resp = cli.get("foo").await.expect("get kv");
+        assert_eq!("bar", resp.kvs[0].value_str());
+        cli.time_to_live(lease.id)
+            .await
+            .expect("lease time to live");
+    }
+    // no read waits for the slow member
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(10, srv2.requests("range"));
+    assert_eq!(10, srv2.requests("lease_time_to_live"));
+}
+
+#[tokio::test]
+async fn test_hedge_unavailable_endpoint() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()])
+        .hedging(Hedging::new().initial_delay(Duration::from_secs(5)));
+    let cli = Client::connect(cfg).await.expect("connect");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    // failed reads are hedged at once, without waiting for the delay
+    srv1.set_unavailable(true);
+    let start = Instant::now();
+    for _ in 0..4 {
+        let resp = cli.get("foo").await.expect("get kv");
+        assert_eq!("bar", resp.kvs[0].value_str());
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(4, srv2.requests("range"));
+
+    // an error is returned once every endpoint has failed
+    srv2.set_unavailable(true);
+    assert!(matches!(cli.get("foo").await, Err(Error::Response(_))));
+}
+
+#[tokio::test]
+async fn test_hedge_rejected_read() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()])
+        .hedging(Hedging::new().initial_delay(Duration::from_secs(5)));
+    let cli = Client::connect(cfg).await.expect("connect");
+
+    // a rejected read is returned at once, and not sent to the other member
+    srv1.fail_next(tonic::Status::invalid_argument("bad range"));
+    srv2.fail_next(tonic::Status::invalid_argument("bad range"));
+    match cli.get("foo").await {
+        Err(Error::Response(status)) => assert_eq!(tonic::Code::InvalidArgument, status.code()),
+        resp => panic!("unexpected response: {resp:?}"),
+    }
+    assert_eq!(1, srv1.requests("range") + srv2.requests("range"));
+}
+
+#[test]
+fn test_hedge_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    metrics::with_local_recorder(&recorder, || {
+        rt.block_on(async {
+            let srv1 = MockEtcdServer::start().await.expect("start mock server");
+            let srv2 = srv1.replica().await.expect("start replica");
+
+            let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()])
+                .hedging(Hedging::new().initial_delay(Duration::from_millis(20)));
+            let cli = Client::connect(cfg).await.expect("connect");
+
+            srv1.set_delay(Duration::from_millis(500));
+            srv2.set_delay(Duration::from_millis(500));
+            cli.get("foo").await.expect("get kv");
+        })
+    });
+
+    let hedged = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .find(|(key, _, _, _)| {
+            key.kind() == MetricKind::Counter
+                && key.key().name() == "etcd_client_hedged_requests_total"
+        })
+        .map(|(_, _, _, value)| value);
+    assert_eq!(Some(DebugValue::Counter(1)), hedged);
+}
@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::timeout;
+
+use etcd_rs::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+type Record = Arc<Mutex<Vec<String>>>;
+
+async fn wait_for(record: &Record, len: usize) -> Vec<String> {
+    timeout(TIMEOUT, async {
+        loop {
+            let record = record.lock().unwrap().clone();
+            if record.len() >= len {
+                return record;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("handlers called")
+}
+
+#[tokio::test]
+async fn test_informer_handlers() {
+    let cli = FakeClient::new();
+    cli.put(("a/1", "1")).await.expect("put kv");
+    let reflector = Reflector::start(cli.clone(), "a/")
+        .await
+        .expect("start reflector");
+
+    let record = Record::default();
+    let informer = Informer::new(reflector)
+        .on_add({
+            let record = Arc::clone(&record);
+            move |kv| {
+                let entry = format!("add {}={}", kv.key_str(), kv.value_str());
+                record.lock().unwrap().push(entry);
+            }
+        })
+        .on_update({
+            let record = Arc::clone(&record);
+            move |old, new| {
+                let entry = format!(
+                    "update {} {}->{}",
+                    new.key_str(),
+                    old.value_str(),
+                    new.value_str()
+                );
+                record.lock().unwrap().push(entry);
+            }
+        })
+        .on_delete({
+            let record = Arc::clone(&record);
+            move |kv| {
+                let entry = format!("delete {}={}", kv.key_str(), kv.value_str());
+                record.lock().unwrap().push(entry);
+            }
+        })
+        .start();
+    timeout(TIMEOUT, informer.wait_for_sync())
+        .await
+        .expect("informer synced")
+        .expect("informer running");
+    assert!(informer.has_synced());
+
+    cli.put(("a/2", "2")).await.expect("put kv");
+    cli.put(("a/1", "11")).await.expect("put kv");
+    cli.put(("b", "b")).await.expect("put kv");
+    cli.delete("a/2").await.expect("delete kv");
+
+    assert_eq!(
+        wait_for(&record, 4).await,
+        ["add a/1=1", "add a/2=2", "update a/1 1->11", "delete a/2=2"]
+    );
+    assert_eq!(
+        "11",
+        informer
+            .reflector()
+            .get("a/1")
+            .expect("cached kv")
+            .value_str()
+    );
+}
+
+#[tokio::test]
+async fn test_informer_resync() {
+    let cli = FakeClient::new();
+    cli.put(("a/1", "1")).await.expect("put kv");
+    let reflector = Reflector::start(cli.clone(), "a/")
+        .await
+        .expect("start reflector");
+
+    let record = Record::default();
+    let _informer = Informer::new(reflector)
+        .on_update({
+            let record = Arc::clone(&record);
+            move |old, new| {
+                assert_eq!(old, new);
+                record.lock().unwrap().push(new.key_str().to_owned());
+            }
+        })
+        .resync_period(Duration::from_millis(50))
+        .start();
+
+    assert_eq!(wait_for(&record, 2).await[..2], ["a/1", "a/1"]);
+}
+
+#[tokio::test]
+async fn test_work_queue_dedup() {
+    let queue = WorkQueue::new();
+    queue.add("a");
+    queue.add("a");
+    queue.add("b");
+    assert_eq!(queue.len(), 2);
+
+    let key = queue.get().await.expect("key");
+    assert_eq!(key, b"a");
+    // added while being processed, so queued again once done
+    queue.add("a");
+    assert_eq!(queue.len(), 1);
+    queue.done(&key);
+    assert_eq!(queue.len(), 2);
+
+    assert_eq!(queue.get().await.expect("key"), b"b");
+    assert_eq!(queue.get().await.expect("key"), b"a");
+    assert!(queue.is_empty());
+
+    queue.shut_down();
+    assert!(queue.get().await.is_none());
+    queue.add("c");
+    assert!(queue.is_empty());
+}
+
+#[tokio::test]
+async fn test_work_queue_rate_limited() {
+    let queue = WorkQueue::with_backoff(Duration::from_millis(10), Duration::from_millis(40));
+    let worker = tokio::spawn({
+        let queue = queue.clone();
+        async move {
+            let mut attempts = 0;
+            while let Some(key) = queue.get().await {
+                attempts += 1;
+                if attempts < 4 {
+                    queue.add_rate_limited(key.clone());
+                } else {
+                    assert_eq!(queue.retries(&key), 3);
+                    queue.forget(&key);
+                    queue.shut_down();
+                }
+                queue.done(&key);
+            }
+            attempts
+        }
+    });
+
+    queue.add("a");
+    let attempts = timeout(TIMEOUT, worker)
+        .await
+        .expect("worker done")
+        .expect("worker");
+    assert_eq!(attempts, 4);
+    assert_eq!(queue.retries("a"), 0);
+}
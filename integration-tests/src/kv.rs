@@ -20,7 +20,23 @@ async fn test_put_error() {
         _ => unreachable!(),
     }
 
-    // hard code max in server-side
+    // hard code max in server-side, rejected by client before sending
+    let resp = cli
+        .put(("foo", "x".repeat((1.5 * (1024 * 1024) as f64) as usize)))
+        .await;
+    match resp {
+        Err(Error::RequestTooLarge { size, limit }) => {
+            assert_eq!(limit, RequestLimits::DEFAULT_MAX_REQUEST_BYTES);
+            assert!(size > limit);
+        }
+        _ => unreachable!(),
+    }
+
+    // server-side limit is still in effect if the client limit is raised
+    let cli = cli.with_request_limits(RequestLimits {
+        max_request_bytes: usize::MAX,
+        ..Default::default()
+    });
     let resp = cli
         .put(("foo", "x".repeat((1.5 * (1024 * 1024) as f64) as usize)))
         .await;
@@ -33,6 +49,24 @@ async fn test_put_error() {
     }
 }
 
+#[tokio::test]
+async fn test_txn_too_many_ops() {
+    let ctx = Context::new(false);
+    let cli = ctx.connect_to_cluster().await;
+
+    let txn = (0..=RequestLimits::DEFAULT_MAX_TXN_OPS).fold(TxnRequest::new(), |txn, i| {
+        txn.and_then(PutRequest::new(format!("foo{}", i), "bar"))
+    });
+
+    match cli.txn(txn).await {
+        Err(Error::TooManyTxnOps { count, limit }) => {
+            assert_eq!(count, RequestLimits::DEFAULT_MAX_TXN_OPS + 1);
+            assert_eq!(limit, RequestLimits::DEFAULT_MAX_TXN_OPS);
+        }
+        _ => unreachable!(),
+    }
+}
+
 #[tokio::test]
 async fn test_put_with_lease() {
     let ctx = Context::new(false);
@@ -245,3 +279,143 @@ async fn test_compact_error() {
         .await
         .expect_err("compact with future revision");
 }
+
+#[tokio::test]
+async fn test_txn_compare_lease() {
+    let ctx = Context::new(false);
+    let cli = ctx.connect_to_cluster().await;
+
+    let lease = cli
+        .grant_lease(Duration::from_secs(10))
+        .await
+        .expect("grant lease");
+
+    cli.put(PutRequest::from(("foo", "bar")).lease(lease.id))
+        .await
+        .expect("put kv with lease");
+
+    let owned = Compare::lease(KeyRange::key("foo"), TxnCmp::Equal, lease.id);
+
+    let resp = cli
+        .txn(
+            TxnRequest::new()
+                .when(owned.clone())
+                .and_then(PutRequest::from(("foo", "baz")).lease(lease.id)),
+        )
+        .await
+        .expect("txn with owned lease");
+    assert!(resp.succeeded);
+
+    let resp = cli
+        .txn(
+            TxnRequest::new()
+                .when_all(vec![
+                    owned,
+                    Compare::value(KeyRange::key("foo"), TxnCmp::Equal, "bar"),
+                ])
+                .and_then(PutRequest::from(("foo", "qux"))),
+        )
+        .await
+        .expect("txn with stale value");
+    assert!(!resp.succeeded);
+
+    let resp = cli
+        .txn(
+            TxnRequest::new()
+                .when_lease(KeyRange::key("foo"), TxnCmp::Equal, 0)
+                .and_then(PutRequest::from(("foo", "qux"))),
+        )
+        .await
+        .expect("txn with no lease");
+    assert!(!resp.succeeded);
+
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("baz", resp.kvs[0].value_str());
+    assert_eq!(lease.id, resp.kvs[0].lease);
+}
+
+#[tokio::test]
+async fn test_txn_typed_responses() {
+    let ctx = Context::new(false);
+    let cli = ctx.connect_to_cluster().await;
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    let mut nested = TxnRequest::new();
+    let nested_get = nested.push_and_then(RangeRequest::from("foo"));
+
+    let mut txn = TxnRequest::new().when_value(KeyRange::key("foo"), TxnCmp::Equal, "bar");
+    let put = txn.push_and_then(PutRequest::from(("foo", "baz")).prev_kv(true));
+    let nested = txn.push_and_then(nested);
+    let delete = txn.push_or_else(DeleteRequest::from("foo"));
+
+    let resp = cli.txn(txn).await.expect("txn");
+    assert!(resp.succeeded);
+
+    assert_eq!(
+        "bar",
+        resp.get(&put).expect("put result").prev_kv.value_str()
+    );
+
+    let range = resp
+        .get(&nested)
+        .expect("nested txn result")
+        .get(&nested_get)
+        .expect("nested range result");
+    assert_eq!("baz", range.kvs[0].value_str());
+
+    match resp.get(&delete) {
+        Err(Error::TxnBranchMismatch) => {}
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn test_bulk_writer() {
+    let ctx = Context::new(false);
+    let cli = ctx.connect_to_cluster().await;
+
+    cli.put(("bulk-stale", "bar")).await.expect("put kv");
+
+    let mut writer = BulkWriter::new(&cli)
+        .limits(RequestLimits {
+            max_txn_ops: 16,
+            ..Default::default()
+        })
+        .concurrency(2);
+    for i in 0..100 {
+        writer.put((format!("bulk-{:03}", i), "foo"));
+    }
+    writer.put(("bulk-000", "bar"));
+    writer.delete("bulk-stale");
+    assert_eq!(writer.len(), 101);
+
+    let resp = writer.commit().await.expect("bulk write");
+    assert!(resp.is_success());
+    assert_eq!(resp.batches.len(), 7);
+    assert_eq!(resp.batches.iter().map(|b| b.ops).sum::<usize>(), 101);
+
+    let resp = cli.get_by_prefix("bulk-").await.expect("get by prefix");
+    assert_eq!(resp.count, 100);
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    let mut writer = BulkWriter::new(&cli)
+        .limits(RequestLimits {
+            max_txn_ops: 16,
+            ..Default::default()
+        })
+        .atomic(true);
+    for i in 0..17 {
+        writer.delete(format!("bulk-{:03}", i));
+    }
+    match writer.commit().await {
+        Err(Error::TooManyTxnOps { count, limit }) => {
+            assert_eq!(count, 17);
+            assert_eq!(limit, 16);
+        }
+        _ => unreachable!(),
+    }
+
+    let resp = cli.get_by_prefix("bulk-").await.expect("get by prefix");
+    assert_eq!(resp.count, 100);
+}
@@ -2,7 +2,28 @@
 
 #[macro_use]
 mod support;
+mod cache;
+mod call_options;
+mod client_config;
+mod client_metrics;
+mod connector;
+mod discovery;
 mod failover;
+mod fake;
+mod health;
+mod hedge;
+mod informer;
 mod kv;
+mod mock_server;
+mod namespace;
+mod read_consistency;
+mod reflector;
+mod routing;
+mod service;
+mod session;
 mod tls;
+mod tls_reload;
+mod tower_layer;
+mod trace;
+mod transport;
 mod watch;
@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::time::timeout;
+use tonic::{Code, Status};
+use tower::ServiceBuilder;
+
+use etcd_rs::*;
+
+async fn connect(srv: &MockEtcdServer) -> Client {
+    Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect to mock server")
+}
+
+fn status_code(err: Error) -> Code {
+    match err {
+        Error::Response(status) => status.code(),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn test_mock_server_kv_watch_lease() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+
+    let (mut stream, cancel) = cli.watch(KeyRange::prefix("foo")).await.expect("watch");
+
+    cli.put(("foo1", "bar")).await.expect("put kv");
+    let resp = cli.get("foo1").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    match stream.inbound().await {
+        WatchInbound::Ready(resp) => {
+            assert_eq!(resp.events.len(), 1);
+            assert_eq!(resp.events[0].kv.key_str(), "foo1");
+        }
+        other => panic!("unexpected watch inbound: {:?}", other),
+    }
+
+    cancel.cancel().await.expect("cancel watch");
+    assert!(matches!(stream.inbound().await, WatchInbound::Closed));
+
+    let lease = cli
+        .grant_lease(Duration::from_secs(10))
+        .await
+        .expect("grant lease");
+    let mut keep_alive = cli.keep_alive_for(lease.id).await.expect("keep alive");
+    let resp = keep_alive.keep_alive().await.expect("keep alive lease");
+    assert_eq!(resp.map(|r| r.ttl), Some(10));
+
+    // the fake client sees the same store
+    assert_eq!(
+        srv.fake_client().revision(),
+        cli.get("foo1").await.unwrap().header.revision()
+    );
+}
+
+#[tokio::test]
+async fn test_mock_server_faults() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+
+    srv.fail_next(Status::internal("boom"));
+    assert_eq!(
+        status_code(cli.put(("foo", "bar")).await.unwrap_err()),
+        Code::Internal
+    );
+    cli.put(("foo", "bar"))
+        .await
+        .expect("put kv after injected error");
+
+    srv.set_unavailable(true);
+    assert_eq!(
+        status_code(cli.get("foo").await.unwrap_err()),
+        Code::Unavailable
+    );
+    srv.set_unavailable(false);
+
+    srv.set_no_leader(true);
+    match cli.get("foo").await.unwrap_err() {
+        Error::Response(status) => assert_eq!(status.message(), "etcdserver: no leader"),
+        e => panic!("unexpected error: {:?}", e),
+    }
+    srv.set_no_leader(false);
+
+    srv.set_delay(Duration::from_millis(500));
+    assert!(timeout(Duration::from_millis(100), cli.get("foo"))
+        .await
+        .is_err());
+    srv.set_delay(Duration::ZERO);
+
+    let revision = cli.put(("foo", "baz")).await.unwrap().header.revision();
+    srv.compact(revision).expect("compact");
+    let err = cli
+        .get(RangeRequest::new(KeyRange::key("foo")).revision(revision - 1))
+        .await
+        .unwrap_err();
+    assert_eq!(status_code(err), Code::OutOfRange);
+}
+
+#[tokio::test]
+async fn test_mock_server_member_headers() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+    assert_ne!(srv1.member_id(), srv2.member_id());
+    let cli = connect(&srv2).await;
+
+    let (mut stream, _cancel) = cli.watch(KeyRange::key("foo")).await.expect("watch");
+    let resp = cli.put(("foo", "bar")).await.expect("put kv");
+    assert_eq!(resp.header.member_id(), srv2.member_id());
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(resp.header.member_id(), srv2.member_id());
+    let resp = cli
+        .txn(TxnRequest::new().and_then(RangeRequest::new(KeyRange::key("foo"))))
+        .await
+        .expect("txn");
+    assert_eq!(resp.header.member_id(), srv2.member_id());
+    let lease = cli
+        .grant_lease(Duration::from_secs(10))
+        .await
+        .expect("grant lease");
+    assert_eq!(lease.header.member_id(), srv2.member_id());
+    let resp = cli
+        .time_to_live(lease.id)
+        .await
+        .expect("lease time to live");
+    assert_eq!(resp.header.member_id(), srv2.member_id());
+
+    loop {
+        match stream.next().await {
+            Some(WatchInbound::Ready(resp)) => {
+                assert_eq!(resp.header.member_id(), srv2.member_id());
+                if !resp.events.is_empty() {
+                    break;
+                }
+            }
+            other => panic!("watch ended: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_mock_server_watch_without_leader() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    srv.set_no_leader(true);
+
+    let cli = connect(&srv).await;
+    assert!(matches!(
+        cli.watch(KeyRange::key("foo")).await,
+        Err(Error::Response(_))
+    ));
+
+    // a watch which does not require a leader is served by the member regardless
+    let layer = ServiceBuilder::new().map_request(|mut req: tonic::codegen::http::Request<_>| {
+        req.headers_mut().remove("hasleader");
+        req
+    });
+    let cli = Client::connect_with_layer(ClientConfig::new([srv.endpoint()]), layer)
+        .await
+        .expect("connect to mock server");
+    let (mut stream, _cancel) = cli.watch(KeyRange::key("foo")).await.expect("watch");
+    srv.fake_client().put(("foo", "bar")).await.expect("put kv");
+    loop {
+        match stream.next().await {
+            Some(WatchInbound::Ready(resp)) if resp.events.is_empty() => {}
+            Some(WatchInbound::Ready(resp)) => {
+                assert_eq!("bar", resp.events[0].kv.value_str());
+                break;
+            }
+            other => panic!("watch ended: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_mock_server_auth() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    srv.add_user("root", "secret");
+
+    let err = connect(&srv).await.put(("foo", "bar")).await.unwrap_err();
+    assert_eq!(status_code(err), Code::InvalidArgument);
+
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]).auth("root", "secret"))
+        .await
+        .expect("connect with auth");
+    cli.put(("foo", "bar")).await.expect("put kv with token");
+
+    srv.expire_tokens();
+    let err = cli.put(("foo", "bar")).await.unwrap_err();
+    assert_eq!(status_code(err), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_mock_server_failover() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cli = Client::connect(ClientConfig::new([srv1.endpoint(), srv2.endpoint()]))
+        .await
+        .expect("connect to mock servers");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    srv1.shutdown().await;
+
+    let mut last_err = None;
+    for _ in 0..10 {
+        match cli.get("foo").await {
+            Ok(resp) => {
+                assert_eq!("bar", resp.kvs[0].value_str());
+                return;
+            }
+            Err(e) => last_err = Some(e),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("failed to fail over: {:?}", last_err);
+}
+
+#[tokio::test]
+async fn test_mock_server_nospace() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = connect(&srv).await;
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    srv.set_nospace(true);
+    let err = cli.put(("foo", "baz")).await.unwrap_err();
+    assert_eq!(status_code(err), Code::ResourceExhausted);
+
+    // puts are rejected in either branch, and in nested transactions
+    let put = || PutRequest::new("foo", "baz");
+    for txn in [
+        TxnRequest::new().and_then(put()),
+        TxnRequest::new().or_else(put()),
+        TxnRequest::new().and_then(TxnRequest::new().and_then(put())),
+    ] {
+        let err = cli.txn(txn).await.unwrap_err();
+        assert_eq!(status_code(err), Code::ResourceExhausted);
+    }
+    cli.txn(TxnRequest::new().and_then(RangeRequest::new(KeyRange::key("foo"))))
+        .await
+        .expect("txn without puts");
+
+    let err = cli.grant_lease(Duration::from_secs(10)).await.unwrap_err();
+    assert_eq!(status_code(err), Code::ResourceExhausted);
+    cli.delete("foo").await.expect("delete kv");
+}
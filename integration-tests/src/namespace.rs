@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use etcd_rs::*;
+
+use crate::support::{Context, KVOp};
+
+#[tokio::test]
+async fn test_namespaced_kv() {
+    let ctx = Context::new(false);
+    let cli = ctx.connect_to_cluster().await;
+    let ns = Namespaced::new(cli.clone(), "tenant-a/");
+
+    cli.put(("foo", "outside")).await.expect("put kv");
+    ns.put(("foo", "bar")).await.expect("put kv in namespace");
+    ns.put(("fop", "baz")).await.expect("put kv in namespace");
+
+    let resp = cli.get("tenant-a/foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    let resp = ns.get_all().await.expect("get all in namespace");
+    assert_eq!(resp.count, 2);
+    assert_eq!("foo", resp.kvs[0].key_str());
+    assert_eq!("fop", resp.kvs[1].key_str());
+
+    let resp = ns
+        .txn(
+            TxnRequest::new()
+                .when_value(KeyRange::key("foo"), TxnCmp::Equal, "bar")
+                .and_then(RangeRequest::from(KeyRange::prefix("fo"))),
+        )
+        .await
+        .expect("txn in namespace");
+    assert!(resp.succeeded);
+    match &resp.responses[0] {
+        TxnOpResponse::Range(resp) => assert_eq!("foo", resp.kvs[0].key_str()),
+        _ => unreachable!(),
+    }
+
+    let resp = ns
+        .delete(DeleteRequest::new(KeyRange::all()).prev_kv(true))
+        .await
+        .expect("delete all in namespace");
+    assert_eq!(resp.deleted, 2);
+    assert_eq!("foo", resp.prev_kvs[0].key_str());
+
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("outside", resp.kvs[0].value_str());
+
+    let lease = ns
+        .grant_lease(Duration::from_secs(60))
+        .await
+        .expect("grant lease");
+    ns.put(PutRequest::new("leased", "bar").lease(lease.id))
+        .await
+        .expect("put kv with lease");
+    cli.put(PutRequest::new("leased", "outside").lease(lease.id))
+        .await
+        .expect("put kv with lease");
+    let resp = ns
+        .time_to_live(LeaseTimeToLiveRequest::new(lease.id).with_keys(true))
+        .await
+        .expect("lease time to live in namespace");
+    assert_eq!(resp.keys, [b"leased".to_vec()]);
+}
+
+#[tokio::test]
+async fn test_namespaced_watch() {
+    let ctx = Context::new(false);
+    let cli = ctx.connect_to_cluster().await;
+    let ns = Namespaced::new(cli.clone(), "tenant-b/");
+
+    let (mut stream, cancel) = ns.watch(KeyRange::all()).await.expect("watch created");
+
+    cli.put(("foo1", "outside")).await.expect("put kv");
+
+    let ops = [
+        KVOp::Put("foo1".to_owned(), "bar1".to_owned()),
+        KVOp::Delete("foo1".to_owned()),
+    ];
+
+    apply_kv_ops!(ns, ops);
+
+    cancel.cancel().await.expect("watch canceled");
+
+    assert_ops_events!(ops, stream);
+}
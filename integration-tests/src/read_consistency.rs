@@ -0,0 +1,68 @@
+use etcd_rs::*;
+
+#[tokio::test]
+async fn test_linearizable_read_without_leader() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect to mock server");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(ReadConsistency::Linearizable, resp.consistency);
+
+    srv.set_no_leader(true);
+    assert!(matches!(cli.get("foo").await, Err(Error::Response(_))));
+}
+
+#[tokio::test]
+async fn test_serializable_read() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect to mock server");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    srv.set_no_leader(true);
+    let resp = cli
+        .with_call_options(CallOptions::new().read_consistency(ReadConsistency::Serializable))
+        .get("foo")
+        .await
+        .expect("serializable get kv");
+    assert_eq!(ReadConsistency::Serializable, resp.consistency);
+    assert_eq!("bar", resp.kvs[0].value_str());
+}
+
+#[tokio::test]
+async fn test_serializable_fallback() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(
+        ClientConfig::new([srv.endpoint()])
+            .read_consistency(ReadConsistency::LinearizableWithSerializableFallback),
+    )
+    .await
+    .expect("connect to mock server");
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(ReadConsistency::Linearizable, resp.consistency);
+
+    // degrades to a stale read during the election
+    srv.set_no_leader(true);
+    let resp = cli.get("foo").await.expect("get kv without leader");
+    assert_eq!(ReadConsistency::Serializable, resp.consistency);
+    assert_eq!("bar", resp.kvs[0].value_str());
+    // the linearizable read failed, then the serializable one was served
+    assert_eq!(3, srv.requests("range"));
+
+    // per-call options override the default
+    assert!(cli
+        .with_call_options(CallOptions::new().read_consistency(ReadConsistency::Linearizable))
+        .get("foo")
+        .await
+        .is_err());
+
+    srv.set_no_leader(false);
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!(ReadConsistency::Linearizable, resp.consistency);
+}
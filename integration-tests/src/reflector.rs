@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::time::timeout;
+
+use etcd_rs::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[tokio::test]
+async fn test_reflector_list_and_watch() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect");
+    cli.put(("a/1", "1")).await.expect("put kv");
+    cli.put(("a/2", "2")).await.expect("put kv");
+    let revision = cli.put(("b", "b")).await.expect("put kv").header.revision();
+
+    let reflector = Reflector::start(cli.clone(), "a/")
+        .await
+        .expect("start reflector");
+    assert_eq!(reflector.revision(), revision);
+    assert_eq!("1", reflector.get("a/1").expect("cached kv").value_str());
+    assert!(reflector.get("b").is_none());
+    let keys = reflector
+        .range(KeyRange::range("a/2", "a/3"))
+        .into_iter()
+        .map(|kv| kv.key_str().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["a/2"]);
+
+    let mut changes = reflector.changes();
+    cli.put(("a/3", "3")).await.expect("put kv");
+    let revision = cli
+        .delete("a/1")
+        .await
+        .expect("delete kv")
+        .header
+        .revision();
+
+    let snapshot = timeout(TIMEOUT, reflector.snapshot_after(revision))
+        .await
+        .expect("cache in sync")
+        .expect("snapshot");
+    assert_eq!(snapshot.revision, revision);
+    let keys = snapshot.kvs.keys().cloned().collect::<Vec<_>>();
+    assert_eq!(keys, [b"a/2".to_vec(), b"a/3".to_vec()]);
+
+    let event = changes.next().await.expect("put event");
+    assert_eq!(event.event_type, EventType::Put);
+    assert_eq!("a/3", event.kv.key_str());
+    assert!(event.prev_kv.is_none());
+
+    let event = changes.next().await.expect("delete event");
+    assert_eq!(event.event_type, EventType::Delete);
+    assert_eq!("a/1", event.kv.key_str());
+    assert_eq!("1", event.prev_kv.expect("previous kv").value_str());
+}
+
+#[tokio::test]
+async fn test_reflector_relists_on_compaction() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+    let addr = srv1
+        .url()
+        .trim_start_matches("http://")
+        .parse()
+        .expect("address of mock server");
+
+    let cli = Client::connect(ClientConfig::new([srv1.endpoint()]))
+        .await
+        .expect("connect");
+    cli.put(("a/1", "1")).await.expect("put kv");
+    cli.put(("a/2", "2")).await.expect("put kv");
+
+    let reflector = Reflector::start(cli, "a/").await.expect("start reflector");
+    let mut changes = reflector.changes();
+
+    // the reflector misses the changes while its member is down, and they are compacted
+    srv1.shutdown().await;
+    let writer = srv2.fake_client();
+    writer.delete("a/1").await.expect("delete kv");
+    writer.put(("a/2", "two")).await.expect("put kv");
+    let revision = writer
+        .put(("a/3", "3"))
+        .await
+        .expect("put kv")
+        .header
+        .revision();
+    writer.compact(revision).await.expect("compact");
+
+    let _srv1 = srv2.replica_at(addr).await.expect("restart mock server");
+    let snapshot = timeout(TIMEOUT, reflector.snapshot_after(revision))
+        .await
+        .expect("cache in sync")
+        .expect("snapshot");
+    assert_eq!(snapshot.revision, revision);
+    assert!(reflector.get("a/1").is_none());
+    assert_eq!("two", reflector.get("a/2").expect("cached kv").value_str());
+    assert_eq!("3", reflector.get("a/3").expect("cached kv").value_str());
+
+    let mut events = vec![];
+    for _ in 0..3 {
+        let event = changes.next().await.expect("event");
+        events.push((event.event_type, event.kv.key_str().to_owned()));
+    }
+    events.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        events,
+        [
+            (EventType::Delete, "a/1".to_owned()),
+            (EventType::Put, "a/2".to_owned()),
+            (EventType::Put, "a/3".to_owned()),
+        ]
+    );
+}
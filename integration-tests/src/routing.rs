@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use etcd_rs::*;
+
+/// Waits until the client routes mutations to the leader.
+async fn wait_leader(cli: &Client, url: &str) {
+    for _ in 0..100 {
+        if cli.leader().as_deref() == Some(url) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("leader did not become {}", url);
+}
+
+#[tokio::test]
+async fn test_route_mutations_to_leader() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+    let srv3 = srv1.replica().await.expect("start replica");
+    let servers = [&srv1, &srv2, &srv3];
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint(), srv3.endpoint()])
+        .routing(RoutingPolicy::Leader);
+    let cli = Client::connect(cfg).await.expect("connect");
+    assert_eq!(Some(srv1.url()), cli.leader().as_deref());
+
+    for i in 0..10 {
+        cli.put((format!("foo{}", i), "bar")).await.expect("put kv");
+        cli.get(format!("foo{}", i)).await.expect("get kv");
+    }
+    cli.delete("foo0").await.expect("delete kv");
+    cli.grant_lease(Duration::from_secs(10))
+        .await
+        .expect("grant lease");
+
+    assert_eq!(10, srv1.requests("put"));
+    assert_eq!(1, srv1.requests("delete_range"));
+    assert_eq!(1, srv1.requests("lease_grant"));
+    // reads are spread across the followers
+    assert_eq!(0, srv1.requests("range"));
+    assert_eq!(10, srv2.requests("range") + srv3.requests("range"));
+    for srv in servers.iter().skip(1) {
+        assert_eq!(0, srv.requests("put"));
+    }
+
+    // a new term is observed in the next response, which discovers the new leader
+    srv2.elect();
+    cli.get("foo1").await.expect("get kv");
+    wait_leader(&cli, srv2.url()).await;
+
+    for i in 0..10 {
+        cli.put((format!("foo{}", i), "baz")).await.expect("put kv");
+    }
+    assert_eq!(10, srv1.requests("put"));
+    assert_eq!(10, srv2.requests("put"));
+}
+
+#[tokio::test]
+async fn test_balance_without_leader() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+    srv1.set_no_leader(true);
+    srv2.set_no_leader(true);
+
+    let cfg = ClientConfig::new([srv1.endpoint(), srv2.endpoint()]).routing(RoutingPolicy::Leader);
+    let cli = Client::connect(cfg).await.expect("connect");
+    assert_eq!(None, cli.leader());
+
+    srv1.set_no_leader(false);
+    srv2.set_no_leader(false);
+    // mutations are balanced until the leader is known
+    for i in 0..10 {
+        cli.put((format!("foo{}", i), "bar")).await.expect("put kv");
+    }
+    assert_eq!(10, srv1.requests("put") + srv2.requests("put"));
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use etcd_rs::{
+    Client, ClientConfig, FakeClient, KeyRange, MockEtcdServer, Namespaced, PutRequest,
+    ReadConsistency, WatchInbound,
+};
+
+#[tokio::test]
+async fn test_dyn_services() {
+    use etcd_rs::service::{KvService, LeaseService, WatchService};
+
+    let fake = FakeClient::new();
+    let kv: Arc<dyn KvService> = Arc::new(fake.clone());
+    let watch: Arc<dyn WatchService> = Arc::new(fake.clone());
+    let lease: Arc<dyn LeaseService> = Arc::new(fake);
+
+    let (mut stream, _) = watch
+        .watch(KeyRange::key("foo").into())
+        .await
+        .expect("watch");
+
+    kv.put(PutRequest::new("foo", "bar")).await.expect("put kv");
+    let resp = kv.get(KeyRange::key("foo").into()).await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    assert!(matches!(stream.inbound().await, WatchInbound::Ready(_)));
+
+    let resp = lease
+        .grant_lease(std::time::Duration::from_secs(10).into())
+        .await
+        .expect("grant lease");
+    assert_eq!(resp.ttl, 10);
+}
+
+#[tokio::test]
+async fn test_dyn_service_decorators() {
+    use etcd_rs::KeyValueOp;
+
+    let fake = FakeClient::new();
+    let kv: Arc<dyn etcd_rs::service::KvService> = Arc::new(fake.clone());
+
+    // a trait object is a client again, so it can be decorated and erased once more
+    let ns: Arc<dyn etcd_rs::service::KvService> = Arc::new(Namespaced::new(kv, "ns/"));
+    ns.put(("foo", "bar")).await.expect("put kv");
+
+    let resp = fake.get("ns/foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    let resp = ns.get_all().await.expect("get all");
+    assert_eq!("foo", resp.kvs[0].key_str());
+}
+
+#[tokio::test]
+async fn test_dyn_service_forwards_client() {
+    use etcd_rs::KeyValueOp;
+
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(
+        ClientConfig::new([srv.endpoint()])
+            .max_request_bytes(4096)
+            .max_txn_ops(16)
+            .read_consistency(ReadConsistency::Serializable),
+    )
+    .await
+    .expect("connect");
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let kv: Arc<dyn etcd_rs::service::KvService> = Arc::new(cli.clone());
+
+    // the limits found by the client size the batches of a BulkWriter over the trait object
+    assert_eq!(kv.request_limits(), cli.request_limits());
+    assert_eq!(kv.request_limits().max_request_bytes, 4096);
+
+    srv.set_lagging(true);
+    cli.put(("foo", "baz")).await.expect("put kv");
+    assert_eq!(
+        "bar",
+        kv.get("foo").await.expect("get kv").kvs[0].value_str()
+    );
+    let resp = kv
+        .get_linearizable("foo")
+        .await
+        .expect("get kv linearizably");
+    assert_eq!("baz", resp.kvs[0].value_str());
+}
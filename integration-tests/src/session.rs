@@ -0,0 +1,55 @@
+use etcd_rs::*;
+
+#[tokio::test]
+async fn test_read_your_writes() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    let cli = Client::connect(ClientConfig::new([srv1.endpoint(), srv2.endpoint()]))
+        .await
+        .expect("connect");
+    let session = cli.consistent_session();
+    session.put(("foo", "bar")).await.expect("put kv");
+
+    srv2.set_lagging(true);
+    let resp = session.put(("foo", "baz")).await.expect("put kv");
+    assert_eq!(resp.header.revision(), session.token());
+
+    for _ in 0..10 {
+        let resp = session.get("foo").await.expect("get kv");
+        assert_eq!("baz", resp.kvs[0].value_str());
+        assert!(resp.header.revision() >= session.token());
+    }
+}
+
+#[tokio::test]
+async fn test_linearizable_after_retries() {
+    let srv1 = MockEtcdServer::start().await.expect("start mock server");
+    let srv2 = srv1.replica().await.expect("start replica");
+
+    // every read is served by the lagging member
+    let cli = Client::connect(ClientConfig::new([srv2.endpoint()]))
+        .await
+        .expect("connect");
+    let session = cli.consistent_session().retries(2);
+    session.put(("foo", "bar")).await.expect("put kv");
+
+    srv2.set_lagging(true);
+    let writer = srv1.fake_client();
+    let resp = writer.put(("foo", "baz")).await.expect("put kv");
+    session.advance(resp.header.revision());
+
+    // serializable reads are stale
+    let resp = cli
+        .with_call_options(CallOptions::new().read_consistency(ReadConsistency::Serializable))
+        .get("foo")
+        .await
+        .expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    let resp = session.get("foo").await.expect("get kv");
+    assert_eq!("baz", resp.kvs[0].value_str());
+    assert_eq!(ReadConsistency::Linearizable, resp.consistency);
+    // the serializable read of the client, 3 stale reads of the session and the linearizable one
+    assert_eq!(5, srv2.requests("range"));
+}
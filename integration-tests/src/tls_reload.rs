@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+
+use etcd_rs::*;
+
+/// PEMs of a CA, and of a server certificate for `localhost` signed by it.
+struct Certs {
+    ca_cert: String,
+    server_cert: String,
+    server_key: String,
+}
+
+impl Certs {
+    fn generate() -> Self {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = params.self_signed(&ca_key).unwrap();
+
+        let server_key = KeyPair::generate().unwrap();
+        let server_cert = CertificateParams::new(vec!["localhost".to_owned()])
+            .unwrap()
+            .signed_by(&server_key, &ca, &ca_key)
+            .unwrap();
+
+        Self {
+            ca_cert: ca.pem(),
+            server_cert: server_cert.pem(),
+            server_key: server_key.serialize_pem(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_tls_ca_only() {
+    let certs = Certs::generate();
+    let srv = MockEtcdServer::start_tls(&certs.server_cert, &certs.server_key)
+        .await
+        .expect("start mock server");
+
+    let endpoint = Endpoint::new(srv.url()).tls_ca("localhost", &certs.ca_cert);
+    let cli = Client::connect(ClientConfig::new([endpoint]))
+        .await
+        .expect("connect with server-only TLS");
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+}
+
+#[tokio::test]
+async fn test_tls_files_reload() {
+    let certs = Certs::generate();
+    let other = Certs::generate();
+    let srv = MockEtcdServer::start_tls(&certs.server_cert, &certs.server_key)
+        .await
+        .expect("start mock server");
+
+    let dir = std::env::temp_dir().join(format!("etcd-rs-tls-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create cert dir");
+    let ca_path = dir.join("ca.pem");
+
+    // starts with the CA of another cluster, which cannot verify the server
+    std::fs::write(&ca_path, &other.ca_cert).expect("write ca cert");
+
+    let endpoint = Endpoint::new(srv.url()).tls_files(TlsFiles::new("localhost", &ca_path));
+    let cfg = ClientConfig::new([endpoint])
+        .tls_reload_interval(Duration::from_millis(50))
+        .request_timeout(Duration::from_millis(500));
+    let cli = Client::connect(cfg).await.expect("connect");
+    assert!(cli.put(("foo", "bar")).await.is_err());
+
+    // the rotated certificate is picked up without reconnecting the client
+    std::fs::write(&ca_path, &certs.ca_cert).expect("rotate ca cert");
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    cli.put(("foo", "bar"))
+        .await
+        .expect("put kv after rotation");
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tonic::transport::Channel;
+use tower::ServiceBuilder;
+
+use etcd_rs::*;
+
+#[tokio::test]
+async fn test_connect_with_layer() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let layer = {
+        let requests = Arc::clone(&requests);
+        ServiceBuilder::new()
+            .timeout(Duration::from_millis(200))
+            .concurrency_limit(8)
+            .map_request(move |req| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                req
+            })
+    };
+
+    let cli = Client::connect_with_layer(ClientConfig::new([srv.endpoint()]), layer)
+        .await
+        .expect("connect with layer");
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+    assert_eq!(requests.load(Ordering::Relaxed), 2);
+
+    srv.set_delay(Duration::from_secs(1));
+    assert!(cli.get("foo").await.is_err(), "request should time out");
+}
+
+#[tokio::test]
+async fn test_client_with_service() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+
+    let channel = Channel::from_shared(srv.url().to_owned())
+        .unwrap()
+        .connect_lazy();
+    let service = ServiceBuilder::new().concurrency_limit(1).service(channel);
+
+    let cli = Client::with_service(service, None);
+    let (a, b) = tokio::join!(cli.put(("foo", "bar")), cli.put(("fop", "baz")));
+    a.expect("put kv");
+    b.expect("put kv");
+
+    assert_eq!(cli.get_all().await.expect("get all").count, 2);
+}
@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::sdk::trace::TracerProvider;
+use opentelemetry::trace::TracerProvider as _;
+use tower::ServiceBuilder;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use etcd_rs::*;
+
+#[derive(Clone, Default)]
+struct Recorder {
+    fields: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl Visit for Recorder {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields
+            .lock()
+            .unwrap()
+            .push((field.name().to_owned(), format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .lock()
+            .unwrap()
+            .push((field.name().to_owned(), value.to_owned()));
+    }
+}
+
+impl<S> Layer<S> for Recorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+        attrs.record(&mut self.clone());
+    }
+
+    fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+        values.record(&mut self.clone());
+    }
+}
+
+impl Recorder {
+    fn values(&self, name: &str) -> Vec<String> {
+        self.fields
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_client_spans() {
+    let recorder = Recorder::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect to mock server");
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+    cli.get_by_prefix("fo").await.expect("get by prefix");
+    srv.fail_next(tonic::Status::unavailable("injected"));
+    cli.get("foo").await.unwrap_err();
+
+    assert_eq!(recorder.values("op"), vec!["put", "range", "range"]);
+    assert_eq!(recorder.values("key"), vec!["foo", "fo", "foo"]);
+    assert_eq!(recorder.values("range_end"), vec!["fp"]);
+    assert_eq!(recorder.values("endpoint")[0], srv.url());
+    assert_eq!(recorder.values("outcome"), vec!["ok", "ok", "Unavailable"]);
+}
+
+#[tokio::test]
+async fn test_trace_context_propagation() {
+    let provider = TracerProvider::builder().build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("etcd-rs-test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let traceparents = Arc::new(Mutex::new(vec![]));
+    let layer = {
+        let traceparents = Arc::clone(&traceparents);
+        ServiceBuilder::new().map_request(move |req: tonic::codegen::http::Request<_>| {
+            if let Some(value) = req.headers().get("traceparent") {
+                traceparents
+                    .lock()
+                    .unwrap()
+                    .push(value.to_str().unwrap().to_owned());
+            }
+            req
+        })
+    };
+
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let cli = Client::connect_with_layer(ClientConfig::new([srv.endpoint()]), layer)
+        .await
+        .expect("connect to mock server");
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+
+    let traceparents = traceparents.lock().unwrap();
+    assert_eq!(traceparents.len(), 1);
+    let parts: Vec<_> = traceparents[0].split('-').collect();
+    assert_eq!(parts.len(), 4);
+    assert_eq!(parts[0], "00");
+    assert_eq!(parts[1].len(), 32);
+    assert_eq!(parts[2].len(), 16);
+}
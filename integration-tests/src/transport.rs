@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tower::ServiceBuilder;
+
+use etcd_rs::*;
+
+#[tokio::test]
+async fn test_max_decoding_message_size() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+    let value = vec![b'x'; 1024 * 1024];
+
+    let cli = Client::connect(ClientConfig::new([srv.endpoint()]))
+        .await
+        .expect("connect to mock server");
+    for i in 0..5 {
+        cli.put((format!("large/{}", i), value.clone()))
+            .await
+            .expect("put kv");
+    }
+
+    // ranges larger than the default limit of tonic are accepted
+    let resp = cli.get_by_prefix("large/").await.expect("get large range");
+    assert_eq!(5, resp.kvs.len());
+
+    let cli =
+        Client::connect(ClientConfig::new([srv.endpoint()]).max_decoding_message_size(1024 * 1024))
+            .await
+            .expect("connect to mock server");
+    match cli.get_by_prefix("large/").await {
+        Err(Error::Response(status)) => assert_eq!(tonic::Code::OutOfRange, status.code()),
+        other => panic!("expected message size error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn test_gzip() {
+    let srv = MockEtcdServer::start().await.expect("start mock server");
+
+    let encodings = Arc::new(Mutex::new(vec![]));
+    let layer = {
+        let encodings = Arc::clone(&encodings);
+        ServiceBuilder::new().map_request(move |req: tonic::codegen::http::Request<_>| {
+            encodings
+                .lock()
+                .unwrap()
+                .push(req.headers().get("grpc-encoding").cloned());
+            req
+        })
+    };
+
+    let cfg = ClientConfig::new([srv.endpoint()])
+        .gzip(true)
+        .http2_keep_alive_timeout(Duration::from_secs(5))
+        .keep_alive_while_idle(true)
+        .tcp_keepalive(Some(Duration::from_secs(30)))
+        .initial_stream_window_size(1024 * 1024)
+        .initial_connection_window_size(4 * 1024 * 1024);
+    let cli = Client::connect_with_layer(cfg, layer)
+        .await
+        .expect("connect to mock server");
+
+    cli.put(("foo", "bar")).await.expect("put kv");
+    let resp = cli.get("foo").await.expect("get kv");
+    assert_eq!("bar", resp.kvs[0].value_str());
+
+    let encodings = encodings.lock().unwrap();
+    assert_eq!(2, encodings.len());
+    for encoding in encodings.iter() {
+        assert_eq!("gzip", encoding.as_ref().expect("grpc-encoding header"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Endpoints of a balanced channel which change at runtime, by discovery, certificate reload and health checks.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::sync::mpsc::Sender;
+use tonic::transport::Channel;
+use tower::discover::Change;
+
+use crate::health::EndpointHealth;
+use crate::{Error, Result};
+
+/// Capacity of the changes of endpoints buffered for the balanced channel.
+const CHANNEL_CAPACITY: usize = 64;
+
+struct Entry {
+    endpoint: tonic::transport::Endpoint,
+    version: u64,
+    health: EndpointHealth,
+}
+
+#[derive(Default)]
+struct State {
+    entries: BTreeMap<String, Entry>,
+    // versions of the endpoints in the balanced channel
+    inserted: HashMap<String, u64>,
+    next_version: u64,
+    // the leader, when reads are routed to the followers
+    excluded: Option<String>,
+}
+
+/// The set of endpoints which keeps the balanced channel in sync.
+///
+/// Unhealthy endpoints are ejected from the channel, unless all of them are unhealthy.
+/// The excluded endpoint is ejected as well, unless it is the only one left.
+pub(crate) struct EndpointSet {
+    state: Mutex<State>,
+    // serializes the changes sent to the channel, so they are applied in order
+    sync: tokio::sync::Mutex<Sender<Change<String, tonic::transport::Endpoint>>>,
+}
+
+impl EndpointSet {
+    pub(crate) fn channel() -> (Channel, Arc<Self>) {
+        let (channel, tx) = Channel::balance_channel(CHANNEL_CAPACITY);
+        let set = Self {
+            state: Mutex::new(State::default()),
+            sync: tokio::sync::Mutex::new(tx),
+        };
+        (channel, Arc::new(set))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sets the endpoints, endpoints which already exist are kept as they are.
+    pub(crate) async fn set(
+        &self,
+        endpoints: Vec<(String, tonic::transport::Endpoint)>,
+    ) -> Result<()> {
+        {
+            let mut state = self.state();
+            state
+                .entries
+                .retain(|url, _| endpoints.iter().any(|(u, _)| u == url));
+            for (url, endpoint) in endpoints {
+                if !state.entries.contains_key(&url) {
+                    let version = state.next_version;
+                    state.next_version += 1;
+                    state.entries.insert(
+                        url.clone(),
+                        Entry {
+                            endpoint,
+                            version,
+                            health: EndpointHealth::new(url),
+                        },
+                    );
+                }
+            }
+        }
+        self.sync().await
+    }
+
+    /// Replaces the endpoint of the URL, which rebuilds its connection.
+    pub(crate) async fn replace(
+        &self,
+        url: &str,
+        endpoint: tonic::transport::Endpoint,
+    ) -> Result<()> {
+        {
+            let mut state = self.state();
+            let version = state.next_version;
+            state.next_version += 1;
+            if let Some(entry) = state.entries.get_mut(url) {
+                entry.endpoint = endpoint;
+                entry.version = version;
+            }
+        }
+        self.sync().await
+    }
+
+    /// Records the health of the endpoint, which ejects or restores it.
+    pub(crate) async fn set_health(&self, health: EndpointHealth) -> Result<()> {
+        {
+            let mut state = self.state();
+            if let Some(entry) = state.entries.get_mut(&health.endpoint) {
+                entry.health = health;
+            }
+        }
+        self.sync().await
+    }
+
+    /// Excludes the endpoint from the channel, such as the leader when reads are sent to followers.
+    pub(crate) async fn exclude(&self, url: Option<String>) -> Result<()> {
+        self.state().excluded = url;
+        self.sync().await
+    }
+
+    /// Returns the endpoints with their versions, which change when they are replaced.
+    pub(crate) fn endpoints(&self) -> Vec<(String, u64, tonic::transport::Endpoint)> {
+        self.state()
+            .entries
+            .iter()
+            .map(|(url, entry)| (url.clone(), entry.version, entry.endpoint.clone()))
+            .collect()
+    }
+
+    pub(crate) fn health(&self) -> Vec<EndpointHealth> {
+        self.state()
+            .entries
+            .values()
+            .map(|entry| entry.health.clone())
+            .collect()
+    }
+
+    /// Waits until the balanced channel is dropped.
+    pub(crate) async fn closed(&self) {
+        let tx = self.sync.lock().await.clone();
+        tx.closed().await
+    }
+
+    /// Sends the changes which bring the balanced channel to the desired endpoints.
+    async fn sync(&self) -> Result<()> {
+        let tx = self.sync.lock().await;
+
+        let changes = {
+            let mut state = self.state();
+            let healthy = state.entries.values().any(|entry| entry.health.healthy);
+            let mut desired: HashMap<&String, &Entry> = state
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.health.healthy || !healthy)
+                .collect();
+            if let Some(excluded) = &state.excluded {
+                if desired.len() > 1 {
+                    desired.remove(excluded);
+                }
+            }
+
+            let mut changes = vec![];
+            for url in state.inserted.keys() {
+                if !desired.contains_key(url) {
+                    changes.push(Change::Remove(url.clone()));
+                }
+            }
+            for (url, entry) in desired.iter() {
+                if state.inserted.get(*url) != Some(&entry.version) {
+                    changes.push(Change::Insert((*url).clone(), entry.endpoint.clone()));
+                }
+            }
+
+            state.inserted = desired
+                .into_iter()
+                .map(|(url, entry)| (url.clone(), entry.version))
+                .collect();
+            changes
+        };
+
+        for change in changes {
+            tx.send(change).await.map_err(|_| Error::ChannelClosed)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,463 @@
+//! A read-through cache of single keys, which is kept fresh by a watch of the keyspace.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::future;
+use tokio::sync::oneshot;
+
+use crate::kv::{
+    CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValue, KeyValueOp,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, ReadConsistency, RequestLimits,
+    TxnRequest, TxnResponse,
+};
+use crate::metrics;
+use crate::proto::etcdserverpb;
+use crate::reflector::WatchLoop;
+use crate::response_header::ResponseHeader;
+use crate::watch::{Event, EventType, WatchOp};
+use crate::Result;
+
+struct Entry {
+    /// The key value, or none if the key does not exist.
+    kv: Option<KeyValue>,
+    header: ResponseHeader,
+    /// The revision the key value is up to date with.
+    revision: i64,
+    fetched_at: Instant,
+    /// Tick of the last use of the entry, its key in the recency order.
+    used: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<Vec<u8>, Entry>,
+    /// Keys of the entries by the tick of their last use, least recently used first.
+    recency: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+    /// Revision of the last response of the watch, the entries are up to date with it at least.
+    watch_revision: i64,
+    /// Latest revision written through the cache, which hits must reflect.
+    written: i64,
+    watching: bool,
+}
+
+impl State {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn insert(&mut self, key: Vec<u8>, mut entry: Entry) {
+        entry.used = self.next_tick();
+        self.recency.insert(entry.used, key.clone());
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.recency.remove(&old.used);
+        }
+    }
+
+    /// Marks the entry of the key as the most recently used.
+    fn touch(&mut self, key: &[u8]) -> Option<&Entry> {
+        let used = self.next_tick();
+        let entry = self.entries.get_mut(key)?;
+        let key = self.recency.remove(&entry.used)?;
+        self.recency.insert(used, key);
+        entry.used = used;
+        Some(entry)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used);
+        }
+    }
+
+    /// Drops the least recently used entry.
+    fn evict(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            self.entries.remove(&key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// Updates the entry of the key of the event, unless the entry is newer.
+    fn apply(&mut self, event: Event) {
+        let Some(entry) = self.entries.get_mut(&event.kv.key) else {
+            return;
+        };
+        if event.kv.mod_revision <= entry.revision {
+            return;
+        }
+
+        entry.revision = event.kv.mod_revision;
+        entry.kv = match event.event_type {
+            EventType::Put => Some(event.kv),
+            EventType::Delete => None,
+        };
+    }
+}
+
+struct Inner {
+    keyspace: KeyRange,
+    state: Mutex<State>,
+}
+
+impl Inner {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        let KeyRange {
+            key: start,
+            range_end,
+        } = &self.keyspace;
+        if range_end.is_empty() {
+            return key == start.as_slice();
+        }
+        key >= start.as_slice() && (range_end == &[0] || key < range_end.as_slice())
+    }
+}
+
+/// CachedKv caches the results of single-key reads, and keeps them up to date with a watch of the keyspace.
+///
+/// A read of a key in the keyspace, without a revision, limit or other options, is served from the cache when
+/// possible. Otherwise it is read from the client, and the result is cached, including when the key does not
+/// exist. The revision of the header of a hit is the revision the cached key value reflects, which is never older
+/// than the last response of the watch, nor than the last write through the cache. A read older than either, such as
+/// one served by a lagging member, is retried linearizably.
+///
+/// Entries expire after their TTL, and the least recently used entry is dropped when the cache is full.
+/// The cache is bypassed while the watch is down. Other reads and writes are sent to the client.
+///
+/// Clones share the cache, which is kept up to date until every clone is dropped.
+#[derive(Clone)]
+pub struct CachedKv<C> {
+    client: C,
+    inner: Arc<Inner>,
+    max_entries: usize,
+    ttl: Duration,
+    _watcher: Arc<oneshot::Sender<()>>,
+}
+
+impl<C> CachedKv<C>
+where
+    C: KeyValueOp + WatchOp + Clone + Send + Sync + 'static,
+{
+    /// Starts watching the keyspace, with a cache of up to 10000 entries for 60 seconds.
+    pub async fn start(client: C, keyspace: impl Into<KeyRange>) -> Result<Self> {
+        let keyspace = keyspace.into();
+        let revision = client
+            .get(KeyRange::key(keyspace.key.clone()))
+            .await?
+            .header
+            .revision();
+        let inner = Arc::new(Inner {
+            keyspace,
+            state: Mutex::new(State {
+                watch_revision: revision,
+                ..Default::default()
+            }),
+        });
+
+        let (tx, rx) = oneshot::channel();
+        let watcher = Watcher {
+            client: client.clone(),
+            inner: Arc::clone(&inner),
+        };
+        let created = watcher.create().await?;
+        tokio::spawn(async move {
+            future::select(rx, Box::pin(watcher.run(Some(created)))).await;
+        });
+
+        Ok(Self {
+            client,
+            inner,
+            max_entries: 10000,
+            ttl: Duration::from_secs(60),
+            _watcher: Arc::new(tx),
+        })
+    }
+
+    /// Sets the maximum number of cached keys.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Sets how long a key value is cached after it is read.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Gets the revision of the last response of the watch.
+    pub fn watch_revision(&self) -> i64 {
+        self.inner.state().watch_revision
+    }
+
+    /// Gets the number of cached keys.
+    pub fn len(&self) -> usize {
+        self.inner.state().entries.len()
+    }
+
+    /// Whether no key is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every cached key.
+    pub fn clear(&self) {
+        self.inner.state().clear();
+    }
+
+    fn cacheable(&self, req: &etcdserverpb::RangeRequest) -> bool {
+        req.range_end.is_empty()
+            && req.revision == 0
+            && req.limit == 0
+            && !req.keys_only
+            && !req.count_only
+            && req.min_mod_revision == 0
+            && req.max_mod_revision == 0
+            && req.min_create_revision == 0
+            && req.max_create_revision == 0
+            && self.inner.contains(&req.key)
+    }
+
+    fn hit(&self, key: &[u8]) -> Option<RangeResponse> {
+        let mut state = self.inner.state();
+        if !state.watching {
+            return None;
+        }
+
+        let entry = state.entries.get(key)?;
+        if entry.fetched_at.elapsed() > self.ttl {
+            state.remove(key);
+            return None;
+        }
+        let revision = entry.revision.max(state.watch_revision);
+        if revision < state.written {
+            return None;
+        }
+
+        let entry = state.touch(key)?;
+        Some(RangeResponse {
+            header: ResponseHeader::from(etcdserverpb::ResponseHeader {
+                cluster_id: entry.header.cluster_id(),
+                member_id: entry.header.member_id(),
+                revision,
+                raft_term: entry.header.raft_term(),
+            }),
+            kvs: entry.kv.clone().into_iter().collect(),
+            has_more: false,
+            count: u64::from(entry.kv.is_some()),
+            consistency: ReadConsistency::Serializable,
+        })
+    }
+
+    /// Caches the result of a read, unless the watch has seen a newer revision which may have changed the key.
+    fn insert(&self, key: Vec<u8>, resp: &RangeResponse) {
+        let revision = resp.header.revision();
+        let mut state = self.inner.state();
+        if self.max_entries == 0 || !state.watching || revision < state.watch_revision {
+            return;
+        }
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.max_entries {
+            state.evict();
+        }
+        state.insert(
+            key,
+            Entry {
+                kv: resp.kvs.first().cloned(),
+                header: resp.header.clone(),
+                revision,
+                fetched_at: Instant::now(),
+                used: 0,
+            },
+        );
+    }
+
+    /// Gets the latest revision seen by the watch or written through the cache, which reads must reflect.
+    fn seen(&self) -> i64 {
+        let state = self.inner.state();
+        state.watch_revision.max(state.written)
+    }
+
+    fn written(&self, revision: i64) {
+        let mut state = self.inner.state();
+        state.written = state.written.max(revision);
+    }
+}
+
+#[async_trait]
+impl<C> KeyValueOp for CachedKv<C>
+where
+    C: KeyValueOp + WatchOp + Clone + Send + Sync + 'static,
+{
+    async fn put<R>(&self, req: R) -> Result<PutResponse>
+    where
+        R: Into<PutRequest> + Send,
+    {
+        let resp = self.client.put(req).await?;
+        self.written(resp.header.revision());
+        Ok(resp)
+    }
+
+    async fn get<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        let req: etcdserverpb::RangeRequest = req.into().into();
+        if !self.cacheable(&req) {
+            return self.client.get(RangeRequest::from(req)).await;
+        }
+
+        if let Some(resp) = self.hit(&req.key) {
+            metrics::cache(true);
+            return Ok(resp);
+        }
+
+        metrics::cache(false);
+        let key = req.key.clone();
+        let mut resp = self.client.get(RangeRequest::from(req.clone())).await?;
+        if resp.header.revision() < self.seen() {
+            // served by a member lagging behind the watch or a write through the cache
+            resp = self
+                .client
+                .get_linearizable(RangeRequest::from(req))
+                .await?;
+        }
+        self.insert(key, &resp);
+        Ok(resp)
+    }
+
+    async fn get_all(&self) -> Result<RangeResponse> {
+        self.client.get_all().await
+    }
+
+    async fn get_by_prefix<K>(&self, p: K) -> Result<RangeResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.client.get_by_prefix(p).await
+    }
+
+    async fn get_range<F, E>(&self, from: F, end: E) -> Result<RangeResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.client.get_range(from, end).await
+    }
+
+    async fn delete<R>(&self, req: R) -> Result<DeleteResponse>
+    where
+        R: Into<DeleteRequest> + Send,
+    {
+        let resp = self.client.delete(req).await?;
+        self.written(resp.header.revision());
+        Ok(resp)
+    }
+
+    async fn delete_all(&self) -> Result<DeleteResponse> {
+        self.delete(KeyRange::all()).await
+    }
+
+    async fn delete_by_prefix<K>(&self, p: K) -> Result<DeleteResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::prefix(p)).await
+    }
+
+    async fn delete_range<F, E>(&self, from: F, end: E) -> Result<DeleteResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::range(from, end)).await
+    }
+
+    async fn txn<R>(&self, req: R) -> Result<TxnResponse>
+    where
+        R: Into<TxnRequest> + Send,
+    {
+        let resp = self.client.txn(req).await?;
+        self.written(resp.header.revision());
+        Ok(resp)
+    }
+
+    async fn compact<R>(&self, req: R) -> Result<CompactResponse>
+    where
+        R: Into<CompactRequest> + Send,
+    {
+        self.client.compact(req).await
+    }
+
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        self.client.get_linearizable(req).await
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        self.client.request_limits()
+    }
+}
+
+/// The background task of a [`CachedKv`], which applies the events of the keyspace to the cache.
+struct Watcher<C> {
+    client: C,
+    inner: Arc<Inner>,
+}
+
+#[async_trait]
+impl<C> WatchLoop for Watcher<C>
+where
+    C: KeyValueOp + WatchOp + Send + Sync + 'static,
+{
+    type Client = C;
+
+    fn client(&self) -> &C {
+        &self.client
+    }
+
+    fn range(&self) -> KeyRange {
+        self.inner.keyspace.clone()
+    }
+
+    fn revision(&self) -> i64 {
+        self.inner.state().watch_revision
+    }
+
+    /// The cache is bypassed while the watch is down.
+    fn set_watching(&self, watching: bool) {
+        self.inner.state().watching = watching;
+    }
+
+    fn apply(&self, events: Vec<Event>, revision: i64) {
+        let mut state = self.inner.state();
+        for event in events {
+            state.apply(event);
+        }
+        state.watch_revision = state.watch_revision.max(revision);
+    }
+
+    /// The events since are lost, so the cache starts over from the current revision.
+    async fn relist(&self) -> Result<()> {
+        let key = self.inner.keyspace.key.clone();
+        let resp = self.client.get(KeyRange::key(key)).await?;
+        let mut state = self.inner.state();
+        state.clear();
+        state.watch_revision = resp.header.revision();
+        Ok(())
+    }
+}
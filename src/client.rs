@@ -1,29 +1,45 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
 use std::time::Duration;
 
 use async_trait::async_trait;
+use http::Uri;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::mpsc::channel;
 use tokio_stream::wrappers::ReceiverStream;
+#[cfg(feature = "gzip")]
+use tonic::codec::CompressionEncoding;
 use tonic::{
-    codegen::InterceptedService,
-    metadata::{Ascii, MetadataValue},
+    body::BoxBody,
+    codegen::{Body, Bytes, InterceptedService},
+    metadata::{Ascii, MetadataMap, MetadataValue},
     service::Interceptor,
     transport::Channel,
-    Request, Status,
+    Code, Request, Status,
 };
+use tower::{layer::util::Identity, util::BoxCloneService, Layer, Service, ServiceExt};
 
 use crate::auth::{AuthOp, AuthenticateRequest, AuthenticateResponse};
+use crate::balance::EndpointSet;
 use crate::cluster::{
     ClusterOp, MemberAddRequest, MemberAddResponse, MemberListRequest, MemberListResponse,
     MemberRemoveRequest, MemberRemoveResponse, MemberUpdateRequest, MemberUpdateResponse,
 };
+use crate::discovery::SrvDiscovery;
+use crate::health::{self, EndpointHealth, HealthCheck};
+use crate::hedge::{Hedger, Hedging};
 use crate::kv::{
     CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValueOp,
-    PutRequest, PutResponse, RangeRequest, RangeResponse, TxnRequest, TxnResponse,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, ReadConsistency, RequestLimits,
+    TxnRequest, TxnResponse,
 };
 use crate::lease::{
     LeaseGrantRequest, LeaseGrantResponse, LeaseId, LeaseKeepAlive, LeaseOp, LeaseRevokeRequest,
     LeaseRevokeResponse, LeaseTimeToLiveRequest, LeaseTimeToLiveResponse,
 };
+use crate::metrics;
 use crate::proto::etcdserverpb;
 use crate::proto::etcdserverpb::cluster_client::ClusterClient;
 use crate::proto::etcdserverpb::LeaseKeepAliveRequest;
@@ -31,6 +47,10 @@ use crate::proto::etcdserverpb::{
     auth_client::AuthClient, kv_client::KvClient, lease_client::LeaseClient,
     watch_client::WatchClient,
 };
+use crate::routing::{LeaderRouter, RoutingPolicy};
+#[cfg(feature = "tls")]
+use crate::tls::{self, TlsFiles};
+use crate::trace::OpSpan;
 use crate::watch::{WatchCanceler, WatchCreateRequest, WatchOp, WatchStream};
 use crate::{Error, Result};
 
@@ -40,7 +60,7 @@ pub struct TokenInterceptor {
 }
 
 impl TokenInterceptor {
-    fn new(token: Option<String>) -> Self {
+    pub(crate) fn new(token: Option<String>) -> Self {
         Self {
             token: token.map(|token: String| MetadataValue::try_from(&token).unwrap()),
         }
@@ -49,6 +69,9 @@ impl TokenInterceptor {
 
 impl Interceptor for TokenInterceptor {
     fn call(&mut self, mut req: tonic::Request<()>) -> std::result::Result<Request<()>, Status> {
+        #[cfg(feature = "opentelemetry")]
+        crate::trace::inject_context(req.metadata_mut());
+
         match &self.token {
             Some(token) => {
                 req.metadata_mut().insert("authorization", token.clone());
@@ -59,11 +82,75 @@ impl Interceptor for TokenInterceptor {
     }
 }
 
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+type BoxTransport = BoxCloneService<http::Request<BoxBody>, http::Response<BoxBody>, BoxError>;
+
+/// Transport is a type-erased tower service which carries the gRPC requests of a [`Client`].
+///
+/// Any service over `http::Request<BoxBody>` can be used, such as a tonic [`Channel`] wrapped in tower layers.
+pub struct Transport {
+    // the lock only makes the service `Sync`, calls go through `&mut self` and never wait on it.
+    inner: Mutex<BoxTransport>,
+}
+
+impl Transport {
+    pub fn new<S, B>(service: S) -> Self
+    where
+        S: Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let service = service
+            .map_response(|resp: http::Response<B>| {
+                resp.map(|body| {
+                    body.map_err(|e| Status::from_error(e.into()))
+                        .boxed_unsync()
+                })
+            })
+            .map_err(Into::into);
+
+        Self {
+            inner: Mutex::new(BoxCloneService::new(service)),
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut BoxTransport {
+        self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clone for Transport {
+    fn clone(&self) -> Self {
+        let inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        Self {
+            inner: Mutex::new(inner.clone()),
+        }
+    }
+}
+
+impl Service<http::Request<BoxBody>> for Transport {
+    type Response = http::Response<BoxBody>;
+    type Error = BoxError;
+    type Future = <BoxTransport as Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.get_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        self.get_mut().call(req)
+    }
+}
+
 #[cfg(feature = "tls")]
 #[derive(Debug, Clone)]
 enum TlsOption {
     None,
     WithConfig(tonic::transport::ClientTlsConfig),
+    Files(TlsFiles),
 }
 
 #[cfg(not(feature = "tls"))]
@@ -72,41 +159,203 @@ enum TlsOption {
     None,
 }
 
+/// Spreads requests across the transports of endpoints in turn.
+struct RoundRobin {
+    transports: Vec<Transport>,
+    // shared by clones, as the generated clients are cloned for every request.
+    next: Arc<AtomicUsize>,
+    ready: Option<usize>,
+}
+
+impl RoundRobin {
+    fn new(transports: Vec<Transport>) -> Self {
+        Self {
+            transports,
+            next: Arc::new(AtomicUsize::new(0)),
+            ready: None,
+        }
+    }
+}
+
+impl Clone for RoundRobin {
+    fn clone(&self) -> Self {
+        Self {
+            transports: self.transports.clone(),
+            next: Arc::clone(&self.next),
+            ready: None,
+        }
+    }
+}
+
+impl Service<http::Request<BoxBody>> for RoundRobin {
+    type Response = http::Response<BoxBody>;
+    type Error = BoxError;
+    type Future = <Transport as Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        let len = self.transports.len();
+        let next = &self.next;
+        let i = *self
+            .ready
+            .get_or_insert_with(|| next.fetch_add(1, Ordering::Relaxed) % len);
+        self.transports[i].poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let i = self
+            .ready
+            .take()
+            .unwrap_or_else(|| self.next.fetch_add(1, Ordering::Relaxed) % self.transports.len());
+        self.transports[i].call(req)
+    }
+}
+
+/// Async IO stream of a connection made by a connector.
+trait Io: AsyncRead + AsyncWrite + Send + 'static {}
+
+impl<T> Io for T where T: AsyncRead + AsyncWrite + Send + 'static {}
+
+type BoxConnector = BoxCloneService<Uri, Pin<Box<dyn Io>>, BoxError>;
+
+fn box_connector<C>(connector: C) -> BoxConnector
+where
+    C: Service<Uri> + Clone + Send + 'static,
+    C::Response: AsyncRead + AsyncWrite + Send + 'static,
+    C::Future: Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    BoxCloneService::new(
+        connector
+            .map_response(|io| Box::pin(io) as Pin<Box<dyn Io>>)
+            .map_err(Into::into),
+    )
+}
+
+#[derive(Clone)]
+struct Connector(Arc<Mutex<BoxConnector>>);
+
+impl std::fmt::Debug for Connector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Connector")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Endpoint {
     url: String,
 
     tls_opt: TlsOption,
+
+    connector: Option<Connector>,
 }
 
 impl Endpoint {
+    /// Creates an endpoint from the URL of etcd server.
+    ///
+    /// Besides `http://` and `https://` URLs, `unix:///path/to/etcd.sock` connects to the Unix socket at the path.
     pub fn new(url: impl Into<String>) -> Self {
         Self {
             url: url.into(),
             tls_opt: TlsOption::None,
+            connector: None,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Dials the endpoint through the connector, such as a proxy, instead of a TCP connection.
+    ///
+    /// The connector is called with the URI of the endpoint and returns the IO stream of the connection,
+    /// which is wrapped with TLS if configured.
+    pub fn connector<C>(mut self, connector: C) -> Self
+    where
+        C: Service<Uri> + Clone + Send + 'static,
+        C::Response: AsyncRead + AsyncWrite + Send + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<BoxError>,
+    {
+        self.connector = Some(Connector(Arc::new(Mutex::new(box_connector(connector)))));
+        self
+    }
+
+    /// Returns the URL to build the channel from, and the connector to dial it if not TCP.
+    fn dial(&self) -> (String, Option<BoxConnector>) {
+        if let Some(Connector(connector)) = &self.connector {
+            let connector = connector.lock().unwrap_or_else(PoisonError::into_inner);
+            return (self.url.clone(), Some(connector.clone()));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = self.url.strip_prefix("unix://") {
+            let path = std::path::PathBuf::from(path);
+            let connector =
+                tower::service_fn(move |_: Uri| tokio::net::UnixStream::connect(path.clone()));
+            // the authority is unused, but required by the channel
+            return (
+                "http://localhost".to_owned(),
+                Some(box_connector(connector)),
+            );
         }
+
+        (self.url.clone(), None)
     }
 
     #[cfg(feature = "tls")]
     pub fn tls_raw(
-        mut self,
+        self,
         domain_name: impl Into<String>,
         ca_cert: impl AsRef<[u8]>,
         client_cert: impl AsRef<[u8]>,
         client_key: impl AsRef<[u8]>,
     ) -> Self {
-        use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+        self.tls_ca(domain_name, ca_cert)
+            .tls_identity(client_cert, client_key)
+    }
 
-        let certificate = Certificate::from_pem(ca_cert);
-        let identity = Identity::from_pem(client_cert, client_key);
+    /// Dials the endpoint with TLS which only verifies the server with the CA certificate,
+    /// such as when authenticating with a password.
+    #[cfg(feature = "tls")]
+    pub fn tls_ca(self, domain_name: impl Into<String>, ca_cert: impl AsRef<[u8]>) -> Self {
+        use tonic::transport::{Certificate, ClientTlsConfig};
 
-        self.tls_opt = TlsOption::WithConfig(
+        self.tls_config(
             ClientTlsConfig::new()
                 .domain_name(domain_name)
-                .ca_certificate(certificate)
-                .identity(identity),
-        );
+                .ca_certificate(Certificate::from_pem(ca_cert)),
+        )
+    }
+
+    /// Adds the client certificate to the TLS config of the endpoint.
+    #[cfg(feature = "tls")]
+    pub fn tls_identity(
+        mut self,
+        client_cert: impl AsRef<[u8]>,
+        client_key: impl AsRef<[u8]>,
+    ) -> Self {
+        use tonic::transport::{ClientTlsConfig, Identity};
+
+        let identity = Identity::from_pem(client_cert, client_key);
+        let config = match self.tls_opt {
+            TlsOption::WithConfig(config) => config,
+            _ => ClientTlsConfig::new(),
+        };
+        self.tls_opt = TlsOption::WithConfig(config.identity(identity));
+        self
+    }
+
+    /// Dials the endpoint with the TLS config.
+    #[cfg(feature = "tls")]
+    pub fn tls_config(mut self, config: tonic::transport::ClientTlsConfig) -> Self {
+        self.tls_opt = TlsOption::WithConfig(config);
+        self
+    }
 
+    /// Dials the endpoint with TLS of the PEM files, which are reloaded when they change.
+    #[cfg(feature = "tls")]
+    pub fn tls_files(mut self, files: TlsFiles) -> Self {
+        self.tls_opt = TlsOption::Files(files);
         self
     }
 
@@ -134,10 +383,7 @@ where
     T: Into<String>,
 {
     fn from(url: T) -> Self {
-        Self {
-            url: url.into(),
-            tls_opt: TlsOption::None,
-        }
+        Self::new(url)
     }
 }
 
@@ -145,21 +391,79 @@ where
 #[derive(Clone, Debug)]
 pub struct ClientConfig {
     pub endpoints: Vec<Endpoint>,
+    /// Discovers the endpoints from DNS SRV records, `endpoints` are not used if set.
+    pub discovery: Option<SrvDiscovery>,
     pub auth: Option<(String, String)>,
     pub connect_timeout: Duration,
+    pub request_timeout: Option<Duration>,
+    pub read_consistency: ReadConsistency,
     pub http2_keep_alive_interval: Duration,
+    pub http2_keep_alive_timeout: Duration,
+    pub keep_alive_while_idle: bool,
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive: Option<Duration>,
+    pub initial_stream_window_size: Option<u32>,
+    pub initial_connection_window_size: Option<u32>,
+    pub max_decoding_message_size: usize,
+    pub max_encoding_message_size: usize,
+    #[cfg(feature = "gzip")]
+    pub gzip: bool,
+    /// How often the certificates of [`TlsFiles`] are read again.
+    #[cfg(feature = "tls")]
+    pub tls_reload_interval: Duration,
+    /// Probes the endpoints periodically, and ejects the unhealthy ones from the balanced channel.
+    pub health_check: Option<HealthCheck>,
+    pub routing: RoutingPolicy,
+    pub hedging: Option<Hedging>,
+    pub max_txn_ops: usize,
+    pub max_request_bytes: usize,
 }
 
 impl ClientConfig {
+    /// Default maximum size of a response message, which matches the default of the official Go client.
+    pub const DEFAULT_MAX_DECODING_MESSAGE_SIZE: usize = i32::MAX as usize;
+
     pub fn new(endpoints: impl Into<Vec<Endpoint>>) -> Self {
         Self {
             endpoints: endpoints.into(),
+            discovery: None,
             auth: None,
             connect_timeout: Duration::from_secs(30),
+            request_timeout: None,
+            read_consistency: ReadConsistency::Linearizable,
             http2_keep_alive_interval: Duration::from_secs(5),
+            http2_keep_alive_timeout: Duration::from_secs(20),
+            keep_alive_while_idle: false,
+            tcp_nodelay: true,
+            tcp_keepalive: None,
+            initial_stream_window_size: None,
+            initial_connection_window_size: None,
+            max_decoding_message_size: Self::DEFAULT_MAX_DECODING_MESSAGE_SIZE,
+            max_encoding_message_size: usize::MAX,
+            #[cfg(feature = "gzip")]
+            gzip: false,
+            #[cfg(feature = "tls")]
+            tls_reload_interval: Duration::from_secs(10),
+            health_check: None,
+            routing: RoutingPolicy::Balanced,
+            hedging: None,
+            max_txn_ops: RequestLimits::DEFAULT_MAX_TXN_OPS,
+            max_request_bytes: RequestLimits::DEFAULT_MAX_REQUEST_BYTES,
         }
     }
 
+    /// Creates a config which discovers the endpoints from the SRV records of the domain with the system DNS resolver.
+    #[cfg(feature = "dns")]
+    pub fn from_srv(domain: impl Into<String>) -> Self {
+        Self::new(vec![]).discovery(SrvDiscovery::new(domain))
+    }
+
+    /// Sets the discovery of endpoints, which replaces the static endpoints.
+    pub fn discovery(mut self, discovery: SrvDiscovery) -> Self {
+        self.discovery = Some(discovery);
+        self
+    }
+
     pub fn auth(mut self, name: impl Into<String>, password: impl Into<String>) -> Self {
         self.auth = Some((name.into(), password.into()));
         self
@@ -170,20 +474,276 @@ impl ClientConfig {
         self
     }
 
+    /// Sets the default timeout of requests, which can be overridden by [`CallOptions`].
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the default consistency of reads, see [`CallOptions::read_consistency`].
+    pub fn read_consistency(mut self, consistency: ReadConsistency) -> Self {
+        self.read_consistency = consistency;
+        self
+    }
+
     pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
         self.http2_keep_alive_interval = interval;
         self
     }
+
+    /// Sets how long to wait for the acknowledgement of a keep-alive ping before closing the connection.
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.http2_keep_alive_timeout = timeout;
+        self
+    }
+
+    /// Sets whether keep-alive pings are sent while there are no open streams.
+    pub fn keep_alive_while_idle(mut self, enabled: bool) -> Self {
+        self.keep_alive_while_idle = enabled;
+        self
+    }
+
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    /// Sets the initial HTTP/2 flow control window size of streams.
+    pub fn initial_stream_window_size(mut self, size: u32) -> Self {
+        self.initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Sets the initial HTTP/2 flow control window size of connections.
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum size of a response message, such as a large range.
+    pub fn max_decoding_message_size(mut self, size: usize) -> Self {
+        self.max_decoding_message_size = size;
+        self
+    }
+
+    /// Sets the maximum size of a request message.
+    pub fn max_encoding_message_size(mut self, size: usize) -> Self {
+        self.max_encoding_message_size = size;
+        self
+    }
+
+    /// Sets whether requests are compressed with gzip, and gzip compressed responses are accepted.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self, enabled: bool) -> Self {
+        self.gzip = enabled;
+        self
+    }
+
+    /// Sets how often the certificates of [`TlsFiles`] are read again, connections are rebuilt when they change.
+    #[cfg(feature = "tls")]
+    pub fn tls_reload_interval(mut self, interval: Duration) -> Self {
+        self.tls_reload_interval = interval;
+        self
+    }
+
+    /// Enables health checking of the endpoints.
+    ///
+    /// Endpoints whose probes fail are ejected from the balanced channel until they pass again,
+    /// unless all of them fail. Endpoints dialed by connectors are not checked.
+    pub fn health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = Some(health_check);
+        self
+    }
+
+    /// Sets how requests are routed to the members, see [`RoutingPolicy`].
+    ///
+    /// Endpoints dialed by connectors are always balanced.
+    pub fn routing(mut self, routing: RoutingPolicy) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// Enables hedging of `get` and lease `time_to_live`, see [`Hedging`].
+    ///
+    /// Hedged reads are sent over channels of their own for each endpoint, which are not refreshed
+    /// by certificate reload or health checking. Reads are not hedged with DNS discovery.
+    pub fn hedging(mut self, hedging: Hedging) -> Self {
+        self.hedging = Some(hedging);
+        self
+    }
+
+    /// Sets the maximum number of operations in a transaction, which should match `--max-txn-ops` of etcd server.
+    pub fn max_txn_ops(mut self, max_txn_ops: usize) -> Self {
+        self.max_txn_ops = max_txn_ops;
+        self
+    }
+
+    /// Sets the maximum size of a request in bytes, which should match `--max-request-bytes` of etcd server.
+    pub fn max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Returns the request limits enforced by the client.
+    pub fn request_limits(&self) -> RequestLimits {
+        RequestLimits {
+            max_txn_ops: self.max_txn_ops,
+            max_request_bytes: self.max_request_bytes,
+        }
+    }
+
+    /// Builds the channel endpoint to dial the URL of the endpoint.
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables, unused_mut))]
+    fn channel_endpoint(&self, url: String, e: &Endpoint) -> Result<tonic::transport::Endpoint> {
+        let mut c = Channel::from_shared(url)?
+            .connect_timeout(self.connect_timeout)
+            .http2_keep_alive_interval(self.http2_keep_alive_interval)
+            .keep_alive_timeout(self.http2_keep_alive_timeout)
+            .keep_alive_while_idle(self.keep_alive_while_idle)
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive)
+            .initial_stream_window_size(self.initial_stream_window_size)
+            .initial_connection_window_size(self.initial_connection_window_size);
+
+        #[cfg(feature = "tls")]
+        {
+            if let TlsOption::WithConfig(tls) = e.tls_opt.clone() {
+                c = c.tls_config(tls)?;
+            }
+        }
+
+        Ok(c)
+    }
+}
+
+/// Options applied to the operations of a [`Client`], see [`Client::with_call_options`].
+#[derive(Clone, Debug, Default)]
+pub struct CallOptions {
+    timeout: Option<Duration>,
+    metadata: MetadataMap,
+    require_leader: Option<bool>,
+    read_consistency: Option<ReadConsistency>,
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the timeout of an operation, [`Error::Timeout`] is returned once it elapsed.
+    ///
+    /// For watch and lease keep-alive, it only covers opening the stream.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Adds gRPC metadata sent along with requests.
+    pub fn metadata(mut self, metadata: MetadataMap) -> Self {
+        self.metadata = merge_metadata(self.metadata, metadata);
+        self
+    }
+
+    /// When set, requests fail instead of hanging if the member has no leader.
+    ///
+    /// Setting it to false overrides a `true` set by the options this is merged onto.
+    pub fn require_leader(mut self, require_leader: bool) -> Self {
+        self.require_leader = Some(require_leader);
+        self
+    }
+
+    /// Sets the consistency of reads, which maps onto `RangeRequest.serializable`.
+    ///
+    /// Requests built as serializable are served as serializable regardless.
+    pub fn read_consistency(mut self, consistency: ReadConsistency) -> Self {
+        self.read_consistency = Some(consistency);
+        self
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Self {
+            timeout: other.timeout.or(self.timeout),
+            metadata: merge_metadata(self.metadata, other.metadata),
+            require_leader: other.require_leader.or(self.require_leader),
+            read_consistency: other.read_consistency.or(self.read_consistency),
+        }
+    }
+
+    fn apply<T>(&self, req: &mut tonic::Request<T>) {
+        let metadata = req.metadata_mut();
+        *metadata = merge_metadata(std::mem::take(metadata), self.metadata.clone());
+
+        if self.require_leader == Some(true) {
+            metadata.insert("hasleader", "true".try_into().unwrap());
+        }
+    }
+}
+
+fn merge_metadata(into: MetadataMap, from: MetadataMap) -> MetadataMap {
+    let mut headers = into.into_headers();
+    headers.extend(from.into_headers());
+    MetadataMap::from_headers(headers)
+}
+
+/// Whether the member failed the request because the cluster has no leader.
+fn is_no_leader(status: &Status) -> bool {
+    status.code() == Code::Unavailable
+        && (status.message().contains("no leader") || status.message().contains("leader changed"))
 }
 
+/// Responses carrying the header of the member which answered.
+trait Headered {
+    fn header(&self) -> Option<&etcdserverpb::ResponseHeader>;
+}
+
+macro_rules! headered {
+    ($($resp:ident),+) => {$(
+        impl Headered for etcdserverpb::$resp {
+            fn header(&self) -> Option<&etcdserverpb::ResponseHeader> {
+                self.header.as_ref()
+            }
+        }
+    )+};
+}
+
+headered!(
+    RangeResponse,
+    PutResponse,
+    DeleteRangeResponse,
+    TxnResponse,
+    LeaseGrantResponse,
+    LeaseRevokeResponse
+);
+
 /// Client is an abstraction for grouping etcd operations and managing underlying network communications.
 #[derive(Clone)]
 pub struct Client {
-    auth_client: AuthClient<InterceptedService<Channel, TokenInterceptor>>,
-    kv_client: KvClient<InterceptedService<Channel, TokenInterceptor>>,
-    watch_client: WatchClient<InterceptedService<Channel, TokenInterceptor>>,
-    cluster_client: ClusterClient<InterceptedService<Channel, TokenInterceptor>>,
-    lease_client: LeaseClient<InterceptedService<Channel, TokenInterceptor>>,
+    auth_client: AuthClient<InterceptedService<Transport, TokenInterceptor>>,
+    kv_client: KvClient<InterceptedService<Transport, TokenInterceptor>>,
+    watch_client: WatchClient<InterceptedService<Transport, TokenInterceptor>>,
+    cluster_client: ClusterClient<InterceptedService<Transport, TokenInterceptor>>,
+    lease_client: LeaseClient<InterceptedService<Transport, TokenInterceptor>>,
+    // clients of mutations, which are routed to the leader by `RoutingPolicy::Leader`
+    leader_kv_client: KvClient<InterceptedService<Transport, TokenInterceptor>>,
+    leader_lease_client: LeaseClient<InterceptedService<Transport, TokenInterceptor>>,
+    limits: RequestLimits,
+    endpoints: Arc<str>,
+    options: CallOptions,
+    endpoint_set: Option<Arc<EndpointSet>>,
+    router: Option<Arc<LeaderRouter>>,
+    hedger: Option<Arc<Hedger<EndpointClients>>>,
+}
+
+/// Clients of the reads hedged to an endpoint.
+#[derive(Clone)]
+struct EndpointClients {
+    kv_client: KvClient<InterceptedService<Transport, TokenInterceptor>>,
+    lease_client: LeaseClient<InterceptedService<Transport, TokenInterceptor>>,
 }
 
 impl Client {
@@ -191,6 +751,21 @@ impl Client {
     ///
     /// For advanced users, it provides the ability to control more details about the connection.
     pub fn with_channel(channel: Channel, token: Option<String>) -> Self {
+        Self::with_service(channel, token)
+    }
+
+    /// Build clients from any tower service carrying gRPC requests.
+    ///
+    /// It allows inserting tower layers, such as timeouts, concurrency limits and load shedding, in front of the [`Channel`].
+    pub fn with_service<S, B>(service: S, token: Option<String>) -> Self
+    where
+        S: Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let channel = Transport::new(service);
         let auth_interceptor = TokenInterceptor::new(token);
 
         let auth_client = AuthClient::with_interceptor(channel.clone(), auth_interceptor.clone());
@@ -202,49 +777,407 @@ impl Client {
 
         Self {
             auth_client,
+            leader_kv_client: kv_client.clone(),
             kv_client,
             watch_client,
             cluster_client,
+            leader_lease_client: lease_client.clone(),
             lease_client,
+            limits: RequestLimits::default(),
+            endpoints: Arc::from(""),
+            options: CallOptions::default(),
+            endpoint_set: None,
+            router: None,
+            hedger: None,
         }
     }
 
+    /// Sets the request limits which are checked before sending requests.
+    pub fn with_request_limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the request limits which are checked before sending requests.
+    pub fn request_limits(&self) -> RequestLimits {
+        self.limits
+    }
+
+    /// Returns the health of the endpoints as of their last probes, see [`ClientConfig::health_check`].
+    ///
+    /// It is empty if the client was not connected by a config with a balanced channel of dynamic endpoints,
+    /// which are used by discovery, certificate reload and health checking.
+    pub fn endpoint_health(&self) -> Vec<EndpointHealth> {
+        self.endpoint_set
+            .as_ref()
+            .map(|set| set.health())
+            .unwrap_or_default()
+    }
+
+    /// Returns the URL of the leader which mutations are routed to, see [`RoutingPolicy::Leader`].
+    pub fn leader(&self) -> Option<String> {
+        self.router.as_ref().and_then(|router| router.leader())
+    }
+
     pub async fn connect_with_token(cfg: &ClientConfig, token: Option<String>) -> Result<Self> {
-        let channel = {
-            let mut endpoints = Vec::with_capacity(cfg.endpoints.len());
-            for e in cfg.endpoints.iter() {
-                let mut c = Channel::from_shared(e.url.clone())?
-                    .connect_timeout(cfg.connect_timeout)
-                    .http2_keep_alive_interval(cfg.http2_keep_alive_interval);
-
-                #[cfg(feature = "tls")]
-                {
-                    if let TlsOption::WithConfig(tls) = e.tls_opt.clone() {
-                        c = c.tls_config(tls)?;
-                    }
+        Self::connect_with_token_and_layer(cfg, token, &Identity::new()).await
+    }
+
+    async fn connect_with_token_and_layer<L, B>(
+        cfg: &ClientConfig,
+        token: Option<String>,
+        layer: &L,
+    ) -> Result<Self>
+    where
+        L: Layer<Channel>,
+        L::Service:
+            Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Future: Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        if let Some(discovery) = &cfg.discovery {
+            let discovered = discovery.resolve().await?;
+
+            let (channel, set) = EndpointSet::channel();
+            let mut endpoints = Vec::with_capacity(discovered.len());
+            for e in discovered.iter() {
+                endpoints.push((e.url.clone(), cfg.channel_endpoint(e.url.clone(), e)?));
+            }
+            set.set(endpoints).await?;
+
+            let refresh_cfg = cfg.clone();
+            discovery.clone().refresh(Arc::clone(&set), move |e| {
+                refresh_cfg.channel_endpoint(e.url.clone(), e)
+            });
+
+            return Self::with_endpoint_set(cfg, token, layer, channel, set, discovery.domain())
+                .await;
+        }
+
+        let urls = cfg
+            .endpoints
+            .iter()
+            .map(|e| e.url.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut endpoints = Vec::with_capacity(cfg.endpoints.len());
+        let mut connectors = Vec::with_capacity(cfg.endpoints.len());
+        #[cfg(feature = "tls")]
+        let mut reloadable = std::collections::HashMap::new();
+        for e in cfg.endpoints.iter() {
+            #[cfg(feature = "tls")]
+            let e = &match &e.tls_opt {
+                TlsOption::Files(files) => {
+                    let pem = files.read().await?;
+                    let loaded = e.clone().tls_config(files.config(&pem));
+                    reloadable.insert(e.url.clone(), (e.clone(), files.clone(), pem));
+                    loaded
                 }
+                _ => e.clone(),
+            };
 
-                endpoints.push(c);
+            let (url, connector) = e.dial();
+            endpoints.push(cfg.channel_endpoint(url, e)?);
+            connectors.push(connector);
+        }
+
+        // certificates are reloaded, unhealthy endpoints are ejected and requests are routed to the leader
+        // by changing the endpoints of balanced channels
+        let dynamic = cfg.health_check.is_some() || cfg.routing != RoutingPolicy::Balanced;
+        #[cfg(feature = "tls")]
+        let dynamic = dynamic || !reloadable.is_empty();
+        if dynamic && connectors.iter().all(Option::is_none) {
+            let (channel, set) = EndpointSet::channel();
+            set.set(
+                cfg.endpoints
+                    .iter()
+                    .map(|e| e.url.clone())
+                    .zip(endpoints.iter().cloned())
+                    .collect(),
+            )
+            .await?;
+
+            #[cfg(feature = "tls")]
+            if !reloadable.is_empty() {
+                let reload_cfg = cfg.clone();
+                tls::reload(
+                    reloadable,
+                    cfg.tls_reload_interval,
+                    Arc::clone(&set),
+                    move |e| {
+                        let (url, _) = e.dial();
+                        reload_cfg.channel_endpoint(url, e)
+                    },
+                );
             }
 
-            Channel::balance_list(endpoints.into_iter())
+            let hedger = Self::hedger(cfg, &token, layer, &endpoints);
+            let client = Self::with_endpoint_set(cfg, token, layer, channel, set, &urls).await?;
+            return Ok(Self { hedger, ..client });
+        }
+
+        let (transport, hedger) = if connectors.iter().all(Option::is_none) {
+            let hedger = Self::hedger(cfg, &token, layer, &endpoints);
+            let transport =
+                Transport::new(layer.layer(Channel::balance_list(endpoints.into_iter())));
+            (transport, hedger)
+        } else {
+            // channels dialed by connectors cannot be balanced by tonic, so each endpoint
+            // gets its own channel and layer, and requests are spread across them in turn.
+            // certificates of such endpoints are not reloaded.
+            let transports = endpoints
+                .into_iter()
+                .zip(connectors)
+                .map(|(e, connector)| {
+                    let channel = match connector {
+                        Some(connector) => e.connect_with_connector_lazy(connector),
+                        None => e.connect_lazy(),
+                    };
+                    Transport::new(layer.layer(channel))
+                })
+                .collect::<Vec<_>>();
+            let hedger = Self::hedger_of(cfg, &token, transports.clone());
+            (Transport::new(RoundRobin::new(transports)), hedger)
         };
 
-        Ok(Self::with_channel(channel, token))
+        Ok(Self {
+            hedger,
+            ..Self::with_service(transport, token).with_config(cfg, &urls)
+        })
+    }
+
+    /// Builds the hedger of reads over channels of their own for the endpoints, see [`ClientConfig::hedging`].
+    fn hedger<L, B>(
+        cfg: &ClientConfig,
+        token: &Option<String>,
+        layer: &L,
+        endpoints: &[tonic::transport::Endpoint],
+    ) -> Option<Arc<Hedger<EndpointClients>>>
+    where
+        L: Layer<Channel>,
+        L::Service:
+            Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Future: Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        cfg.hedging.as_ref()?;
+        let transports = endpoints
+            .iter()
+            .map(|e| Transport::new(layer.layer(e.connect_lazy())))
+            .collect();
+        Self::hedger_of(cfg, token, transports)
+    }
+
+    fn hedger_of(
+        cfg: &ClientConfig,
+        token: &Option<String>,
+        transports: Vec<Transport>,
+    ) -> Option<Arc<Hedger<EndpointClients>>> {
+        let hedging = cfg.hedging.clone()?;
+        // reads are hedged to another endpoint only
+        if transports.len() < 2 {
+            return None;
+        }
+
+        let clients = transports
+            .into_iter()
+            .map(|transport| {
+                let client = Self::with_service(transport, token.clone()).with_codec_options(cfg);
+                EndpointClients {
+                    kv_client: client.kv_client,
+                    lease_client: client.lease_client,
+                }
+            })
+            .collect();
+        Some(Arc::new(Hedger::new(hedging, clients)))
+    }
+
+    /// Builds the client over the balanced channel of the endpoint set, then starts checking the health
+    /// of the endpoints and routing mutations to the leader as configured.
+    async fn with_endpoint_set<L, B>(
+        cfg: &ClientConfig,
+        token: Option<String>,
+        layer: &L,
+        channel: Channel,
+        set: Arc<EndpointSet>,
+        endpoints: &str,
+    ) -> Result<Self>
+    where
+        L: Layer<Channel>,
+        L::Service:
+            Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Future: Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        if let Some(health_check) = &cfg.health_check {
+            health::check(health_check.clone(), Arc::clone(&set));
+        }
+
+        let mut client = Self::with_service(layer.layer(channel), token.clone());
+        if cfg.routing == RoutingPolicy::Leader {
+            let (channel, writes) = EndpointSet::channel();
+            let router = LeaderRouter::start(Arc::clone(&set), writes, token.clone()).await?;
+            let leader = Self::with_service(layer.layer(channel), token);
+            client.leader_kv_client = leader.kv_client;
+            client.leader_lease_client = leader.lease_client;
+            client.router = Some(router);
+        }
+
+        Ok(Self {
+            endpoint_set: Some(set),
+            ..client.with_config(cfg, endpoints)
+        })
+    }
+
+    /// Applies the settings of the config, which are not part of the connection.
+    fn with_config(self, cfg: &ClientConfig, endpoints: &str) -> Self {
+        Self {
+            endpoints: Arc::from(endpoints),
+            options: CallOptions {
+                timeout: cfg.request_timeout,
+                read_consistency: Some(cfg.read_consistency),
+                ..Default::default()
+            },
+            ..self
+                .with_request_limits(cfg.request_limits())
+                .with_codec_options(cfg)
+        }
+    }
+
+    /// Applies the message size limits and compression of the config to every generated client.
+    fn with_codec_options(mut self, cfg: &ClientConfig) -> Self {
+        macro_rules! configure {
+            ($($client:ident),+) => {$(
+                let client = self
+                    .$client
+                    .max_decoding_message_size(cfg.max_decoding_message_size)
+                    .max_encoding_message_size(cfg.max_encoding_message_size);
+
+                #[cfg(feature = "gzip")]
+                let client = if cfg.gzip {
+                    client
+                        .send_compressed(CompressionEncoding::Gzip)
+                        .accept_compressed(CompressionEncoding::Gzip)
+                } else {
+                    client
+                };
+
+                self.$client = client;
+            )+};
+        }
+
+        configure!(
+            auth_client,
+            kv_client,
+            watch_client,
+            cluster_client,
+            lease_client,
+            leader_kv_client,
+            leader_lease_client
+        );
+        self
+    }
+
+    /// Returns a client which applies the call options to every operation.
+    ///
+    /// Options which are not set fall back to the options of this client.
+    pub fn with_call_options(&self, options: CallOptions) -> Self {
+        let mut cli = self.clone();
+        cli.options = self.options.clone().merge(options);
+        cli
+    }
+
+    /// Returns the call options applied to every operation.
+    pub fn call_options(&self) -> &CallOptions {
+        &self.options
+    }
+
+    fn span(&self, op: &'static str) -> OpSpan {
+        OpSpan::new(op, &self.endpoints).timeout(self.options.timeout)
+    }
+
+    /// Observes the raft term of the response for the leader router, a mutation which failed to reach
+    /// the leader discovers it again.
+    fn routed<T: Headered>(
+        &self,
+        resp: std::result::Result<tonic::Response<T>, Status>,
+    ) -> std::result::Result<T, Status> {
+        if let Some(router) = &self.router {
+            match &resp {
+                Ok(resp) => {
+                    if let Some(header) = resp.get_ref().header() {
+                        router.observe(header.raft_term);
+                    }
+                }
+                Err(status) if status.code() == Code::Unavailable => router.invalidate(),
+                Err(_) => {}
+            }
+        }
+        resp.map(tonic::Response::into_inner)
+    }
+
+    /// Sends the range request, which is hedged across the endpoints if configured.
+    async fn range(
+        &self,
+        req: etcdserverpb::RangeRequest,
+    ) -> std::result::Result<tonic::Response<etcdserverpb::RangeResponse>, Status> {
+        match &self.hedger {
+            Some(hedger) => {
+                hedger
+                    .run("range", |mut c| {
+                        let req = self.request(req.clone());
+                        async move { c.kv_client.range(req).await }
+                    })
+                    .await
+            }
+            None => self.kv_client.clone().range(self.request(req)).await,
+        }
+    }
+
+    fn request<T>(&self, msg: T) -> tonic::Request<T> {
+        let mut req = tonic::Request::new(msg);
+        self.options.apply(&mut req);
+        req
     }
 
     /// Connects to etcd cluster and returns a client.
     ///
     /// # Errors
     /// Will returns `Err` if failed to contact with given endpoints or authentication failed.
-    pub async fn connect(mut cfg: ClientConfig) -> Result<Self> {
-        let cli = Self::connect_with_token(&cfg, None).await?;
+    pub async fn connect(cfg: ClientConfig) -> Result<Self> {
+        Self::connect_with_layer(cfg, Identity::new()).await
+    }
+
+    /// Connects to etcd cluster with the tower layer applied on top of the connection, and returns a client.
+    ///
+    /// If any endpoint is dialed by a connector, including Unix sockets, the layer is applied to each endpoint.
+    ///
+    /// # Errors
+    /// Will returns `Err` if failed to contact with given endpoints or authentication failed.
+    pub async fn connect_with_layer<L, B>(mut cfg: ClientConfig, layer: L) -> Result<Self>
+    where
+        L: Layer<Channel>,
+        L::Service:
+            Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Future: Send + 'static,
+        <L::Service as Service<http::Request<BoxBody>>>::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let cli = Self::connect_with_token_and_layer(&cfg, None, &layer).await?;
 
         match cfg.auth.take() {
             Some((name, password)) => {
                 let token = cli.authenticate((name, password)).await?.token;
 
-                Self::connect_with_token(&cfg, Some(token)).await
+                Self::connect_with_token_and_layer(&cfg, Some(token), &layer).await
             }
             None => Ok(cli),
         }
@@ -257,10 +1190,20 @@ impl AuthOp for Client {
     where
         R: Into<AuthenticateRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.auth_client.clone().authenticate(req).await?;
-
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::AuthenticateRequest = req.into().into();
+
+        self.span("authenticate")
+            .request(&req)
+            .run_unary(async {
+                Ok(self
+                    .auth_client
+                    .clone()
+                    .authenticate(self.request(req))
+                    .await?
+                    .into_inner())
+            })
+            .await
+            .map(Into::into)
     }
 }
 
@@ -270,20 +1213,64 @@ impl KeyValueOp for Client {
     where
         R: Into<PutRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.kv_client.clone().put(req).await?;
+        let req = req.into();
+        req.check_limits(&self.limits)?;
+
+        let req: etcdserverpb::PutRequest = req.into();
+        let span = self.span("put").key(&req.key, &[]).lease_id(req.lease);
 
-        Ok(resp.into_inner().into())
+        span.request(&req)
+            .run_unary(async {
+                Ok(self.routed(self.leader_kv_client.clone().put(self.request(req)).await)?)
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn get<R>(&self, req: R) -> Result<RangeResponse>
     where
         R: Into<RangeRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.kv_client.clone().range(req).await?;
+        let mut req: etcdserverpb::RangeRequest = req.into().into();
+        let consistency = match self.options.read_consistency {
+            _ if req.serializable => ReadConsistency::Serializable,
+            Some(consistency) => consistency,
+            None => ReadConsistency::Linearizable,
+        };
+        req.serializable = consistency == ReadConsistency::Serializable;
+        let span = self
+            .span("range")
+            .key(&req.key, &req.range_end)
+            .revision(req.revision);
+
+        let mut served = match consistency {
+            ReadConsistency::Serializable => ReadConsistency::Serializable,
+            _ => ReadConsistency::Linearizable,
+        };
+        let resp = span
+            .request(&req)
+            .run_unary(async {
+                let fallback = (consistency
+                    == ReadConsistency::LinearizableWithSerializableFallback)
+                    .then(|| etcdserverpb::RangeRequest {
+                        serializable: true,
+                        ..req.clone()
+                    });
+
+                match (self.range(req).await, fallback) {
+                    (Err(status), Some(req)) if is_no_leader(&status) => {
+                        served = ReadConsistency::Serializable;
+                        Ok(self.routed(self.range(req).await)?)
+                    }
+                    (resp, _) => Ok(self.routed(resp)?),
+                }
+            })
+            .await?;
 
-        Ok(resp.into_inner().into())
+        Ok(RangeResponse {
+            consistency: served,
+            ..resp.into()
+        })
     }
 
     async fn get_all(&self) -> Result<RangeResponse> {
@@ -309,10 +1296,23 @@ impl KeyValueOp for Client {
     where
         R: Into<DeleteRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.kv_client.clone().delete_range(req).await?;
-
-        Ok(resp.into_inner().into())
+        let req = req.into();
+        req.check_limits(&self.limits)?;
+
+        let req: etcdserverpb::DeleteRangeRequest = req.into();
+        let span = self.span("delete_range").key(&req.key, &req.range_end);
+
+        span.request(&req)
+            .run_unary(async {
+                Ok(self.routed(
+                    self.leader_kv_client
+                        .clone()
+                        .delete_range(self.request(req))
+                        .await,
+                )?)
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn delete_all(&self) -> Result<DeleteResponse> {
@@ -338,20 +1338,54 @@ impl KeyValueOp for Client {
     where
         R: Into<TxnRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.kv_client.clone().txn(req).await?;
+        let req = req.into();
+        req.check_limits(&self.limits)?;
+
+        let req: etcdserverpb::TxnRequest = req.into();
+        let span = match req.compare.first() {
+            Some(cmp) => self.span("txn").key(&cmp.key, &cmp.range_end),
+            None => self.span("txn"),
+        };
 
-        Ok(resp.into_inner().into())
+        span.request(&req)
+            .run_unary(async {
+                Ok(self.routed(self.leader_kv_client.clone().txn(self.request(req)).await)?)
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn compact<R>(&self, req: R) -> Result<CompactResponse>
     where
         R: Into<CompactRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.kv_client.clone().compact(req).await?;
+        let req: etcdserverpb::CompactionRequest = req.into().into();
+        let span = self.span("compact").revision(req.revision);
+
+        span.request(&req)
+            .run_unary(async {
+                Ok(self
+                    .kv_client
+                    .clone()
+                    .compact(self.request(req))
+                    .await?
+                    .into_inner())
+            })
+            .await
+            .map(Into::into)
+    }
 
-        Ok(resp.into_inner().into())
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        self.with_call_options(CallOptions::new().read_consistency(ReadConsistency::Linearizable))
+            .get(req)
+            .await
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        self.limits
     }
 }
 
@@ -361,34 +1395,46 @@ impl WatchOp for Client {
     where
         R: Into<WatchCreateRequest> + Send,
     {
-        let (tx, rx) = channel::<etcdserverpb::WatchRequest>(128);
+        let req: etcdserverpb::WatchCreateRequest = req.into().into();
+        let span = self
+            .span("watch")
+            .key(&req.key, &req.range_end)
+            .revision(req.start_revision);
 
-        tx.send(req.into().into()).await?;
+        span.run(async {
+            let (tx, rx) = channel::<etcdserverpb::WatchRequest>(128);
 
-        let mut req = tonic::Request::new(ReceiverStream::new(rx));
+            tx.send(WatchCreateRequest::from(req).into()).await?;
 
-        req.metadata_mut()
-            .insert("hasleader", "true".try_into().unwrap());
+            let mut req = self.request(metrics::sent_stream("watch", ReceiverStream::new(rx)));
 
-        let resp = self.watch_client.clone().watch(req).await?;
+            // watch always requires leader, otherwise it would be stuck on a partitioned member
+            req.metadata_mut()
+                .insert("hasleader", "true".try_into().unwrap());
 
-        let mut inbound = resp.into_inner();
+            let resp = self.watch_client.clone().watch(req).await?;
 
-        let watch_id = match inbound.message().await? {
-            Some(resp) => {
-                if !resp.created {
-                    return Err(Error::WatchEvent(
-                        "should receive created event at first".to_owned(),
-                    ));
+            let mut inbound = resp.into_inner();
+
+            let watch_id = match inbound.message().await? {
+                Some(resp) => {
+                    if !resp.created {
+                        return Err(Error::WatchEvent(
+                            "should receive created event at first".to_owned(),
+                        ));
+                    }
+                    assert!(resp.events.is_empty(), "received created event {:?}", resp);
+                    resp.watch_id
                 }
-                assert!(resp.events.is_empty(), "received created event {:?}", resp);
-                resp.watch_id
-            }
 
-            None => return Err(Error::CreateWatch),
-        };
+                None => return Err(Error::CreateWatch),
+            };
+
+            let inbound = metrics::received_stream("watch", metrics::ACTIVE_WATCHES, inbound);
 
-        Ok((WatchStream::new(inbound), WatchCanceler::new(watch_id, tx)))
+            Ok((WatchStream::new(inbound), WatchCanceler::new(watch_id, tx)))
+        })
+        .await
     }
 }
 
@@ -398,24 +1444,84 @@ impl LeaseOp for Client {
     where
         R: Into<LeaseGrantRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.lease_client.clone().lease_grant(req).await?;
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::LeaseGrantRequest = req.into().into();
+        let span = self.span("lease_grant").lease_id(req.id);
+
+        span.request(&req)
+            .run_unary(async {
+                Ok(self.routed(
+                    self.leader_lease_client
+                        .clone()
+                        .lease_grant(self.request(req))
+                        .await,
+                )?)
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn revoke<R>(&self, req: R) -> Result<LeaseRevokeResponse>
     where
         R: Into<LeaseRevokeRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.lease_client.clone().lease_revoke(req).await?;
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::LeaseRevokeRequest = req.into().into();
+        let span = self.span("lease_revoke").lease_id(req.id);
+
+        span.request(&req)
+            .run_unary(async {
+                Ok(self.routed(
+                    self.leader_lease_client
+                        .clone()
+                        .lease_revoke(self.request(req))
+                        .await,
+                )?)
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn keep_alive_for(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive> {
+        let span = self.span("lease_keep_alive").lease_id(lease_id);
+        span.run(self.open_keep_alive(lease_id)).await
+    }
+
+    async fn time_to_live<R>(&self, req: R) -> Result<LeaseTimeToLiveResponse>
+    where
+        R: Into<LeaseTimeToLiveRequest> + Send,
+    {
+        let req: etcdserverpb::LeaseTimeToLiveRequest = req.into().into();
+        let span = self.span("lease_time_to_live").lease_id(req.id);
+
+        span.request(&req)
+            .run_unary(async {
+                let resp = match &self.hedger {
+                    Some(hedger) => {
+                        hedger
+                            .run("lease_time_to_live", |mut c| {
+                                let req = self.request(req.clone());
+                                async move { c.lease_client.lease_time_to_live(req).await }
+                            })
+                            .await
+                    }
+                    None => {
+                        self.lease_client
+                            .clone()
+                            .lease_time_to_live(self.request(req))
+                            .await
+                    }
+                };
+                Ok(resp?.into_inner())
+            })
+            .await
+            .map(Into::into)
+    }
+}
+
+impl Client {
+    async fn open_keep_alive(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive> {
         let (req_tx, req_rx) = channel(1024);
 
-        let req_rx = ReceiverStream::new(req_rx);
+        let req_rx = metrics::sent_stream("lease_keep_alive", ReceiverStream::new(req_rx));
 
         let initial_req = LeaseKeepAliveRequest { id: lease_id };
 
@@ -427,7 +1533,7 @@ impl LeaseOp for Client {
         let mut resp_rx = self
             .lease_client
             .clone()
-            .lease_keep_alive(req_rx)
+            .lease_keep_alive(self.request(req_rx))
             .await?
             .into_inner();
 
@@ -438,16 +1544,10 @@ impl LeaseOp for Client {
             }
         };
 
-        Ok(LeaseKeepAlive::new(lease_id, req_tx, resp_rx))
-    }
+        let resp_rx =
+            metrics::received_stream("lease_keep_alive", metrics::KEEP_ALIVE_LEASES, resp_rx);
 
-    async fn time_to_live<R>(&self, req: R) -> Result<LeaseTimeToLiveResponse>
-    where
-        R: Into<LeaseTimeToLiveRequest> + Send,
-    {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.lease_client.clone().lease_time_to_live(req).await?;
-        Ok(resp.into_inner().into())
+        Ok(LeaseKeepAlive::new(lease_id, req_tx, resp_rx))
     }
 }
 
@@ -457,36 +1557,76 @@ impl ClusterOp for Client {
     where
         R: Into<MemberAddRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.cluster_client.clone().member_add(req).await?;
-
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::MemberAddRequest = req.into().into();
+
+        self.span("member_add")
+            .request(&req)
+            .run_unary(async {
+                Ok(self
+                    .cluster_client
+                    .clone()
+                    .member_add(self.request(req))
+                    .await?
+                    .into_inner())
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn member_remove<R>(&self, req: R) -> Result<MemberRemoveResponse>
     where
         R: Into<MemberRemoveRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.cluster_client.clone().member_remove(req).await?;
-
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::MemberRemoveRequest = req.into().into();
+
+        self.span("member_remove")
+            .request(&req)
+            .run_unary(async {
+                Ok(self
+                    .cluster_client
+                    .clone()
+                    .member_remove(self.request(req))
+                    .await?
+                    .into_inner())
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn member_update<R>(&self, req: R) -> Result<MemberUpdateResponse>
     where
         R: Into<MemberUpdateRequest> + Send,
     {
-        let req = tonic::Request::new(req.into().into());
-        let resp = self.cluster_client.clone().member_update(req).await?;
-
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::MemberUpdateRequest = req.into().into();
+
+        self.span("member_update")
+            .request(&req)
+            .run_unary(async {
+                Ok(self
+                    .cluster_client
+                    .clone()
+                    .member_update(self.request(req))
+                    .await?
+                    .into_inner())
+            })
+            .await
+            .map(Into::into)
     }
 
     async fn member_list(&self) -> Result<MemberListResponse> {
-        let req = tonic::Request::new(MemberListRequest::new().into());
-        let resp = self.cluster_client.clone().member_list(req).await?;
-
-        Ok(resp.into_inner().into())
+        let req: etcdserverpb::MemberListRequest = MemberListRequest::new().into();
+
+        self.span("member_list")
+            .request(&req)
+            .run_unary(async {
+                Ok(self
+                    .cluster_client
+                    .clone()
+                    .member_list(self.request(req))
+                    .await?
+                    .into_inner())
+            })
+            .await
+            .map(Into::into)
     }
 }
@@ -0,0 +1,344 @@
+//! Loading [`ClientConfig`] from etcdctl compatible environment variables and config files.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::client::{ClientConfig, Endpoint};
+use crate::{Error, Result};
+
+/// Settings named after the global flags of etcdctl.
+#[derive(Debug, Default)]
+#[cfg_attr(
+    any(feature = "config-toml", feature = "config-yaml"),
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields, rename_all = "kebab-case")
+)]
+struct Settings {
+    endpoints: Vec<String>,
+    discovery_srv: Option<String>,
+    discovery_srv_name: Option<String>,
+    cacert: Option<PathBuf>,
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+    user: Option<String>,
+    password: Option<String>,
+    dial_timeout: Option<String>,
+    command_timeout: Option<String>,
+    keepalive_time: Option<String>,
+    keepalive_timeout: Option<String>,
+    max_request_bytes: Option<usize>,
+    max_recv_bytes: Option<usize>,
+}
+
+impl Settings {
+    const DEFAULT_ENDPOINT: &'static str = "127.0.0.1:2379";
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| var(&format!("ETCDCTL_{}", name)).filter(|v| !v.is_empty());
+        let bytes = |name: &str| {
+            var(name)
+                .map(|v| {
+                    v.parse()
+                        .map_err(|_| Error::Config(format!("invalid ETCDCTL_{}: {}", name, v)))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            endpoints: var("ENDPOINTS")
+                .map(|v| v.split(',').map(|e| e.trim().to_owned()).collect())
+                .unwrap_or_default(),
+            discovery_srv: var("DISCOVERY_SRV"),
+            discovery_srv_name: var("DISCOVERY_SRV_NAME"),
+            cacert: var("CACERT").map(PathBuf::from),
+            cert: var("CERT").map(PathBuf::from),
+            key: var("KEY").map(PathBuf::from),
+            user: var("USER"),
+            password: var("PASSWORD"),
+            dial_timeout: var("DIAL_TIMEOUT"),
+            command_timeout: var("COMMAND_TIMEOUT"),
+            keepalive_time: var("KEEPALIVE_TIME"),
+            keepalive_timeout: var("KEEPALIVE_TIMEOUT"),
+            max_request_bytes: bytes("MAX_REQUEST_BYTES")?,
+            max_recv_bytes: bytes("MAX_RECV_BYTES")?,
+        })
+    }
+
+    async fn into_config(self) -> Result<ClientConfig> {
+        let tls: Option<TlsSettings> = match (self.cacert, self.cert, self.key) {
+            (None, None, None) => None,
+            (ca, None, None) => Some((ca, None)),
+            (ca, Some(cert), Some(key)) => Some((ca, Some((cert, key)))),
+            _ => {
+                return Err(Error::Config(
+                    "cert and key must be set together".to_owned(),
+                ))
+            }
+        };
+
+        let urls = if self.endpoints.is_empty() {
+            vec![Self::DEFAULT_ENDPOINT.to_owned()]
+        } else {
+            self.endpoints
+        };
+
+        let mut endpoints = Vec::with_capacity(urls.len());
+        for url in urls {
+            let url = match (url.contains("://"), tls.is_some()) {
+                (true, _) => url,
+                (false, true) => format!("https://{}", url),
+                (false, false) => format!("http://{}", url),
+            };
+            endpoints.push(match &tls {
+                Some((ca, identity)) if url.starts_with("https://") => {
+                    tls_endpoint(url, ca.as_deref(), identity.as_ref()).await?
+                }
+                _ => Endpoint::new(url),
+            });
+        }
+
+        let mut cfg = ClientConfig::new(endpoints);
+
+        if let Some(domain) = self.discovery_srv {
+            cfg = discovery(cfg, domain, self.discovery_srv_name, tls.as_ref()).await?;
+        }
+
+        match (self.user, self.password) {
+            (Some(user), Some(password)) => cfg = cfg.auth(user, password),
+            (Some(user), None) => match user.split_once(':') {
+                Some((name, password)) => cfg = cfg.auth(name, password),
+                None => {
+                    return Err(Error::Config(format!(
+                        "password of user {} is not set",
+                        user
+                    )))
+                }
+            },
+            (None, Some(_)) => {
+                return Err(Error::Config("password is set without user".to_owned()))
+            }
+            (None, None) => {}
+        }
+
+        if let Some(v) = self.dial_timeout {
+            cfg = cfg.connect_timeout(parse_duration(&v)?);
+        }
+        if let Some(v) = self.command_timeout {
+            cfg = cfg.request_timeout(parse_duration(&v)?);
+        }
+        if let Some(v) = self.keepalive_time {
+            cfg = cfg.http2_keep_alive_interval(parse_duration(&v)?);
+        }
+        if let Some(v) = self.keepalive_timeout {
+            cfg = cfg.http2_keep_alive_timeout(parse_duration(&v)?);
+        }
+        if let Some(v) = self.max_request_bytes {
+            cfg = cfg.max_request_bytes(v);
+        }
+        if let Some(v) = self.max_recv_bytes {
+            cfg = cfg.max_decoding_message_size(v);
+        }
+
+        Ok(cfg)
+    }
+}
+
+/// The CA certificate and the client certificate and key.
+type TlsSettings = (Option<PathBuf>, Option<(PathBuf, PathBuf)>);
+
+/// Paths of the certificates, the server is verified with the system roots without the CA certificate.
+#[cfg(feature = "tls")]
+fn tls_files(
+    domain_name: &str,
+    ca: Option<&Path>,
+    identity: Option<&(PathBuf, PathBuf)>,
+) -> Result<crate::TlsFiles> {
+    let mut files = match ca {
+        Some(ca) => crate::TlsFiles::new(domain_name, ca),
+        #[cfg(feature = "tls-roots")]
+        None => crate::TlsFiles::system_roots(domain_name),
+        #[cfg(not(feature = "tls-roots"))]
+        None => {
+            return Err(Error::Config(
+                "TLS without cacert requires the tls-roots feature".to_owned(),
+            ))
+        }
+    };
+    if let Some((cert, key)) = identity {
+        files = files.identity(cert, key);
+    }
+    Ok(files)
+}
+
+/// Dials the endpoint with the certificate files, which are reloaded when they change.
+#[cfg(feature = "tls")]
+async fn tls_endpoint(
+    url: String,
+    ca: Option<&Path>,
+    identity: Option<&(PathBuf, PathBuf)>,
+) -> Result<Endpoint> {
+    let uri: http::Uri = url.parse()?;
+    let files = tls_files(uri.host().unwrap_or_default(), ca, identity)?;
+
+    // fails early if the files cannot be read
+    files.read().await?;
+    Ok(Endpoint::new(url).tls_files(files))
+}
+
+#[cfg(not(feature = "tls"))]
+async fn tls_endpoint(
+    _url: String,
+    _ca: Option<&Path>,
+    _identity: Option<&(PathBuf, PathBuf)>,
+) -> Result<Endpoint> {
+    Err(Error::Config("TLS requires the tls feature".to_owned()))
+}
+
+#[cfg(feature = "dns")]
+async fn discovery(
+    cfg: ClientConfig,
+    domain: String,
+    name: Option<String>,
+    tls: Option<&TlsSettings>,
+) -> Result<ClientConfig> {
+    let mut discovery = crate::SrvDiscovery::new(domain);
+    if let Some(name) = name {
+        discovery = discovery.service_name(name);
+    }
+    if let Some((ca, identity)) = tls {
+        discovery = discovery_tls(discovery, ca.as_deref(), identity.as_ref()).await?;
+    }
+    Ok(cfg.discovery(discovery))
+}
+
+#[cfg(not(feature = "dns"))]
+async fn discovery(
+    _cfg: ClientConfig,
+    _domain: String,
+    _name: Option<String>,
+    _tls: Option<&TlsSettings>,
+) -> Result<ClientConfig> {
+    Err(Error::Config(
+        "discovery-srv requires the dns feature".to_owned(),
+    ))
+}
+
+/// Dials the discovered `_etcd-client-ssl._tcp` endpoints with the certificates, which are read once.
+#[cfg(all(feature = "dns", feature = "tls"))]
+async fn discovery_tls(
+    discovery: crate::SrvDiscovery,
+    ca: Option<&Path>,
+    identity: Option<&(PathBuf, PathBuf)>,
+) -> Result<crate::SrvDiscovery> {
+    // the domain name is replaced by the target of each record
+    let files = tls_files(discovery.domain(), ca, identity)?;
+    let pem = files.read().await?;
+    Ok(discovery.tls_config(files.config(&pem)))
+}
+
+#[cfg(all(feature = "dns", not(feature = "tls")))]
+async fn discovery_tls(
+    _discovery: crate::SrvDiscovery,
+    _ca: Option<&Path>,
+    _identity: Option<&(PathBuf, PathBuf)>,
+) -> Result<crate::SrvDiscovery> {
+    Err(Error::Config("TLS requires the tls feature".to_owned()))
+}
+
+/// Parses a duration in the format of Go, such as `5s`, `500ms` or `1m30s`.
+fn parse_duration(s: &str) -> Result<Duration> {
+    let invalid = || Error::Config(format!("invalid duration: {}", s));
+
+    let mut rest = s.trim();
+    if rest == "0" {
+        return Ok(Duration::ZERO);
+    }
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let n = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(invalid)?;
+        let value: f64 = rest[..n].parse().map_err(|_| invalid())?;
+        rest = &rest[n..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let secs = match &rest[..unit_len] {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit_len..];
+
+        total += Duration::try_from_secs_f64(value * secs).map_err(|_| invalid())?;
+    }
+
+    Ok(total)
+}
+
+impl ClientConfig {
+    /// Creates a config from the environment variables of etcdctl.
+    ///
+    /// `ETCDCTL_ENDPOINTS`, `ETCDCTL_DISCOVERY_SRV`, `ETCDCTL_DISCOVERY_SRV_NAME`, `ETCDCTL_CACERT`, `ETCDCTL_CERT`,
+    /// `ETCDCTL_KEY`, `ETCDCTL_USER`, `ETCDCTL_PASSWORD`, `ETCDCTL_DIAL_TIMEOUT`, `ETCDCTL_COMMAND_TIMEOUT`,
+    /// `ETCDCTL_KEEPALIVE_TIME`, `ETCDCTL_KEEPALIVE_TIMEOUT`, `ETCDCTL_MAX_REQUEST_BYTES` and `ETCDCTL_MAX_RECV_BYTES`
+    /// are supported. Like etcdctl, endpoints default to `127.0.0.1:2379`, and endpoints without a scheme use
+    /// `https` when the CA certificate or the client certificate is set. Without the CA certificate, the server is
+    /// verified with the system roots, which requires the `tls-roots` feature. The certificate files are reloaded
+    /// when they change, see [`TlsFiles`](crate::TlsFiles), except for endpoints discovered by `ETCDCTL_DISCOVERY_SRV`.
+    ///
+    /// # Errors
+    /// Will returns `Err` if a variable is invalid or the certificates cannot be read.
+    pub async fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok()).await
+    }
+
+    /// Creates a config from the variables of etcdctl looked up by the function, such as from a map,
+    /// see [`ClientConfig::from_env`].
+    ///
+    /// # Errors
+    /// Will returns `Err` if a variable is invalid or the certificates cannot be read.
+    pub async fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        Settings::from_vars(var)?.into_config().await
+    }
+
+    /// Creates a config from a TOML or YAML file, chosen by the extension of the path.
+    ///
+    /// The keys are the names of etcdctl flags, such as `endpoints`, `cacert`, `user` and `dial-timeout`,
+    /// with the same meanings as [`ClientConfig::from_env`]. TOML requires the `config-toml` feature,
+    /// and YAML requires the `config-yaml` feature.
+    ///
+    /// # Errors
+    /// Will returns `Err` if the file is invalid or cannot be read.
+    #[cfg(any(feature = "config-toml", feature = "config-yaml"))]
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = tokio::fs::read_to_string(path).await?;
+
+        let settings: Settings = match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "config-toml")]
+            Some("toml") => toml::from_str(&content).map_err(|e| Error::Config(e.to_string()))?,
+            #[cfg(feature = "config-yaml")]
+            Some("yaml" | "yml") => {
+                serde_yaml::from_str(&content).map_err(|e| Error::Config(e.to_string()))?
+            }
+            _ => {
+                return Err(Error::Config(format!(
+                    "unsupported config file: {}",
+                    path.display()
+                )));
+            }
+        };
+
+        settings.into_config().await
+    }
+}
@@ -0,0 +1,280 @@
+//! Discovery of cluster endpoints from DNS SRV records.
+//!
+//! Like etcd, `_etcd-client-ssl._tcp.<domain>` records are dialed with TLS and `_etcd-client._tcp.<domain>`
+//! records without. The records are resolved again periodically, and the endpoints of the client follow them.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::{self, Either};
+
+use crate::balance::EndpointSet;
+use crate::client::Endpoint;
+use crate::{Error, Result};
+
+/// A DNS SRV record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub target: String,
+    pub port: u16,
+}
+
+impl SrvRecord {
+    pub fn new(target: impl Into<String>, port: u16) -> Self {
+        Self {
+            target: target.into(),
+            port,
+        }
+    }
+}
+
+/// Resolves DNS SRV records.
+///
+/// A name without records resolves to an empty list rather than an error.
+#[async_trait]
+pub trait SrvResolver: Send + Sync {
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>>;
+}
+
+/// Resolver of the system DNS configuration.
+#[cfg(feature = "dns")]
+#[derive(Default)]
+pub struct SystemResolver {
+    inner: tokio::sync::OnceCell<hickory_resolver::TokioAsyncResolver>,
+}
+
+#[cfg(feature = "dns")]
+#[async_trait]
+impl SrvResolver for SystemResolver {
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>> {
+        use hickory_resolver::error::ResolveErrorKind;
+
+        let resolver = self
+            .inner
+            .get_or_try_init(|| async {
+                hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()
+                    .map_err(|e| Error::Discovery(e.to_string()))
+            })
+            .await?;
+
+        match resolver.srv_lookup(name).await {
+            Ok(lookup) => Ok(lookup
+                .iter()
+                .map(|srv| {
+                    let target = srv.target().to_utf8();
+                    SrvRecord::new(target.trim_end_matches('.'), srv.port())
+                })
+                .collect()),
+            Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(vec![]),
+            Err(e) => Err(Error::Discovery(e.to_string())),
+        }
+    }
+}
+
+/// Discovers the endpoints of a cluster from the SRV records of a domain.
+#[derive(Clone)]
+pub struct SrvDiscovery {
+    domain: String,
+    service_name: Option<String>,
+    resolver: Arc<dyn SrvResolver>,
+    refresh_interval: Duration,
+    #[cfg(feature = "tls")]
+    tls: Option<tonic::transport::ClientTlsConfig>,
+}
+
+impl SrvDiscovery {
+    pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// Creates a discovery of the domain with the system DNS resolver.
+    #[cfg(feature = "dns")]
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self::with_resolver(domain, SystemResolver::default())
+    }
+
+    /// Creates a discovery of the domain with the specified resolver.
+    pub fn with_resolver(domain: impl Into<String>, resolver: impl SrvResolver + 'static) -> Self {
+        Self {
+            domain: domain.into(),
+            service_name: None,
+            resolver: Arc::new(resolver),
+            refresh_interval: Self::DEFAULT_REFRESH_INTERVAL,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Sets the service name, which looks up `_etcd-client-<name>._tcp` like `--discovery-srv-name` of etcd.
+    pub fn service_name(mut self, name: impl Into<String>) -> Self {
+        self.service_name = Some(name.into());
+        self
+    }
+
+    /// Sets how often the records are resolved again.
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Sets the certificates to dial the endpoints of `_etcd-client-ssl._tcp` records,
+    /// the target of each record is verified as the domain name.
+    #[cfg(feature = "tls")]
+    pub fn tls_raw(
+        self,
+        ca_cert: impl AsRef<[u8]>,
+        client_cert: impl AsRef<[u8]>,
+        client_key: impl AsRef<[u8]>,
+    ) -> Self {
+        use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+        self.tls_config(
+            ClientTlsConfig::new()
+                .ca_certificate(Certificate::from_pem(ca_cert))
+                .identity(Identity::from_pem(client_cert, client_key)),
+        )
+    }
+
+    /// Sets the TLS config to dial the endpoints of `_etcd-client-ssl._tcp` records,
+    /// such as one with only the CA certificate. The target of each record is verified as the domain name.
+    #[cfg(feature = "tls")]
+    pub fn tls_config(mut self, config: tonic::transport::ClientTlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    fn srv_name(&self, ssl: bool) -> String {
+        let mut service = "_etcd-client".to_owned();
+        if ssl {
+            service.push_str("-ssl");
+        }
+        if let Some(name) = &self.service_name {
+            service.push('-');
+            service.push_str(name);
+        }
+        format!("{}._tcp.{}", service, self.domain)
+    }
+
+    /// Resolves the records into endpoints.
+    ///
+    /// # Errors
+    /// Will returns `Err` if no endpoint is found, or `_etcd-client-ssl._tcp` records are found without TLS configured.
+    pub async fn resolve(&self) -> Result<Vec<Endpoint>> {
+        let (ssl_name, plain_name) = (self.srv_name(true), self.srv_name(false));
+        let (ssl, plain) = futures::join!(
+            self.resolver.lookup_srv(&ssl_name),
+            self.resolver.lookup_srv(&plain_name),
+        );
+
+        let mut seen = HashSet::new();
+        let mut endpoints = vec![];
+        let mut error = None;
+
+        match ssl {
+            Ok(records) => {
+                for record in records {
+                    if seen.insert((record.target.clone(), record.port)) {
+                        endpoints.push(self.tls_endpoint(record)?);
+                    }
+                }
+            }
+            Err(e) => error = Some(e),
+        }
+
+        match plain {
+            Ok(records) => {
+                for record in records {
+                    if seen.insert((record.target.clone(), record.port)) {
+                        endpoints.push(Endpoint::new(format!(
+                            "http://{}:{}",
+                            record.target, record.port
+                        )));
+                    }
+                }
+            }
+            Err(e) => error = error.or(Some(e)),
+        }
+
+        if endpoints.is_empty() {
+            return Err(error.unwrap_or_else(|| {
+                Error::Discovery(format!("no SRV records found for {}", self.domain))
+            }));
+        }
+
+        Ok(endpoints)
+    }
+
+    /// Resolves the records periodically and updates the endpoints of the set, until the balanced channel is dropped.
+    ///
+    /// Failed resolutions keep the current endpoints, as do resolutions whose endpoints all fail to build.
+    /// Endpoints that fail to build are skipped, and logged with the `tracing` feature.
+    pub(crate) fn refresh<F>(self, endpoints: Arc<EndpointSet>, build: F)
+    where
+        F: Fn(&Endpoint) -> Result<tonic::transport::Endpoint> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            loop {
+                let sleep = tokio::time::sleep(self.refresh_interval);
+                if let Either::Left(_) =
+                    future::select(Box::pin(endpoints.closed()), Box::pin(sleep)).await
+                {
+                    return;
+                }
+
+                let Ok(discovered) = self.resolve().await else {
+                    continue;
+                };
+                let mut built = Vec::with_capacity(discovered.len());
+                for e in &discovered {
+                    match build(e) {
+                        Ok(endpoint) => built.push((e.url().to_owned(), endpoint)),
+                        #[cfg(feature = "tracing")]
+                        Err(err) => {
+                            tracing::warn!(endpoint = e.url(), error = %err, "skipping discovered etcd endpoint")
+                        }
+                        #[cfg(not(feature = "tracing"))]
+                        Err(_) => {}
+                    }
+                }
+                if built.is_empty() {
+                    continue;
+                }
+                if endpoints.set(built).await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    #[cfg(feature = "tls")]
+    fn tls_endpoint(&self, record: SrvRecord) -> Result<Endpoint> {
+        let tls = self.tls.as_ref().ok_or_else(|| {
+            Error::Discovery(format!("TLS is not configured for {}", self.srv_name(true)))
+        })?;
+
+        let url = format!("https://{}:{}", record.target, record.port);
+        Ok(Endpoint::new(url).tls_config(tls.clone().domain_name(record.target)))
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn tls_endpoint(&self, _record: SrvRecord) -> Result<Endpoint> {
+        Err(Error::Discovery(format!(
+            "TLS is not supported for {}",
+            self.srv_name(true)
+        )))
+    }
+}
+
+impl std::fmt::Debug for SrvDiscovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SrvDiscovery")
+            .field("domain", &self.domain)
+            .field("service_name", &self.service_name)
+            .field("refresh_interval", &self.refresh_interval)
+            .finish()
+    }
+}
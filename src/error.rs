@@ -22,4 +22,18 @@ pub enum Error {
     WatchChannelSend(#[from] tokio::sync::mpsc::error::SendError<etcdserverpb::WatchRequest>),
     #[error("watch event exhausted")]
     WatchEventExhausted,
+    #[error("transaction operation is on the branch which was not executed")]
+    TxnBranchMismatch,
+    #[error("transaction operation response mismatch at {0}")]
+    TxnOpMismatch(usize),
+    #[error("request size {size} bytes exceeds the limit of {limit} bytes")]
+    RequestTooLarge { size: usize, limit: usize },
+    #[error("transaction has {count} operations which exceeds the limit of {limit}")]
+    TooManyTxnOps { count: usize, limit: usize },
+    #[error("request timed out")]
+    Timeout,
+    #[error("failed to discover endpoints: {0}")]
+    Discovery(String),
+    #[error("invalid config: {0}")]
+    Config(String),
 }
@@ -0,0 +1,178 @@
+//! Active health checking of endpoints, which ejects unhealthy endpoints from the balanced channel.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either};
+use tonic::transport::Channel;
+use tonic::Code;
+
+use crate::balance::EndpointSet;
+use crate::metrics;
+use crate::proto::etcdserverpb;
+use crate::proto::etcdserverpb::kv_client::KvClient;
+use crate::proto::etcdserverpb::maintenance_client::MaintenanceClient;
+
+/// How endpoints are probed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthProbe {
+    /// `Maintenance::Status`, which fails if the member has no leader or raised an alarm, such as NOSPACE.
+    Status,
+    /// A serializable `Range`, which is answered by the member locally.
+    SerializableRange,
+}
+
+/// Config of the health checking of endpoints.
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    interval: Duration,
+    timeout: Duration,
+    probe: HealthProbe,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthCheck {
+    /// Probes with `Maintenance::Status` every 5 seconds, with a timeout of 2 seconds.
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(2),
+            probe: HealthProbe::Status,
+        }
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn probe(mut self, probe: HealthProbe) -> Self {
+        self.probe = probe;
+        self
+    }
+}
+
+/// Health of an endpoint, as of the last probe.
+#[derive(Debug, Clone)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    /// Endpoints are healthy until a probe fails.
+    pub healthy: bool,
+    /// Why the last probe failed.
+    pub error: Option<String>,
+    /// When the endpoint was probed last.
+    pub checked_at: Option<Instant>,
+}
+
+impl EndpointHealth {
+    pub(crate) fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            healthy: true,
+            error: None,
+            checked_at: None,
+        }
+    }
+}
+
+/// Probes the endpoints of the set periodically, until the balanced channel is dropped.
+pub(crate) fn check(config: HealthCheck, endpoints: Arc<EndpointSet>) {
+    tokio::spawn(async move {
+        // connections of probes, which are rebuilt when the endpoints are replaced
+        let mut channels: HashMap<String, (u64, Channel)> = HashMap::new();
+
+        loop {
+            let current = endpoints.endpoints();
+            channels.retain(|url, _| current.iter().any(|(u, _, _)| u == url));
+
+            let probes = current.into_iter().map(|(url, version, endpoint)| {
+                let channel = match channels.get(&url) {
+                    Some((v, channel)) if *v == version => channel.clone(),
+                    _ => {
+                        let channel = endpoint.connect_lazy();
+                        channels.insert(url.clone(), (version, channel.clone()));
+                        channel
+                    }
+                };
+                let config = &config;
+                async move {
+                    let result = tokio::time::timeout(config.timeout, probe(config.probe, channel))
+                        .await
+                        .unwrap_or_else(|_| Err("health check timed out".to_owned()));
+                    metrics::endpoint_health(&url, result.is_ok());
+
+                    EndpointHealth {
+                        endpoint: url,
+                        healthy: result.is_ok(),
+                        error: result.err(),
+                        checked_at: Some(Instant::now()),
+                    }
+                }
+            });
+
+            for health in future::join_all(probes).await {
+                if endpoints.set_health(health).await.is_err() {
+                    return;
+                }
+            }
+
+            let sleep = tokio::time::sleep(config.interval);
+            if let Either::Left(_) =
+                future::select(Box::pin(endpoints.closed()), Box::pin(sleep)).await
+            {
+                return;
+            }
+        }
+    });
+}
+
+async fn probe(probe: HealthProbe, channel: Channel) -> std::result::Result<(), String> {
+    let result = match probe {
+        HealthProbe::Status => MaintenanceClient::new(channel)
+            .status(etcdserverpb::StatusRequest {})
+            .await
+            .map(|resp| {
+                let resp = resp.into_inner();
+                if resp.leader == 0 {
+                    Err("no leader".to_owned())
+                } else if !resp.errors.is_empty() {
+                    Err(resp.errors.join(", "))
+                } else {
+                    Ok(())
+                }
+            }),
+        HealthProbe::SerializableRange => KvClient::new(channel)
+            .range(etcdserverpb::RangeRequest {
+                key: b"health".to_vec(),
+                serializable: true,
+                ..Default::default()
+            })
+            .await
+            .map(|_| Ok(())),
+    };
+
+    match result {
+        Ok(result) => result,
+        // the member answered, though the probe is not authorized
+        Err(status)
+            if matches!(
+                status.code(),
+                Code::Unauthenticated | Code::PermissionDenied | Code::InvalidArgument
+            ) =>
+        {
+            Ok(())
+        }
+        Err(status) => Err(format!("{:?}: {}", status.code(), status.message())),
+    }
+}
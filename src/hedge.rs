@@ -0,0 +1,194 @@
+//! Hedging of idempotent reads across the endpoints, which cuts the tail latency caused by a slow member.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either};
+use futures::stream::{FuturesUnordered, StreamExt};
+use tonic::{Code, Status};
+
+use crate::metrics;
+
+/// Number of latencies the hedging delay is computed from.
+const LATENCY_WINDOW: usize = 128;
+
+/// Latencies observed before the percentile is used instead of the initial delay.
+const MIN_SAMPLES: usize = 16;
+
+/// Config of hedged reads, see [`ClientConfig::hedging`](crate::ClientConfig::hedging).
+///
+/// A read which has not been answered within the delay is sent to another endpoint as well,
+/// and the first successful response wins. The delay is the percentile of the latencies of recent reads.
+#[derive(Debug, Clone)]
+pub struct Hedging {
+    percentile: f64,
+    initial_delay: Duration,
+    min_delay: Duration,
+    max_hedges: usize,
+}
+
+impl Default for Hedging {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hedging {
+    /// Hedges a read once, when it takes longer than the 95th percentile of recent reads.
+    pub fn new() -> Self {
+        Self {
+            percentile: 0.95,
+            initial_delay: Duration::from_millis(50),
+            min_delay: Duration::from_millis(1),
+            max_hedges: 1,
+        }
+    }
+
+    /// Sets the percentile of the latencies of recent reads used as the delay, between 0 and 1.
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the delay used until enough reads have been observed.
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Sets the lower bound of the delay, so fast reads are not hedged all the time.
+    pub fn min_delay(mut self, delay: Duration) -> Self {
+        self.min_delay = delay;
+        self
+    }
+
+    /// Sets how many other endpoints a read is sent to at most.
+    pub fn max_hedges(mut self, max_hedges: usize) -> Self {
+        self.max_hedges = max_hedges;
+        self
+    }
+}
+
+/// Sends reads to the clients of the endpoints, and hedges them to the next endpoints.
+pub(crate) struct Hedger<C> {
+    config: Hedging,
+    endpoints: Vec<C>,
+    next: AtomicUsize,
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl<C: Clone> Hedger<C> {
+    pub(crate) fn new(config: Hedging, endpoints: Vec<C>) -> Self {
+        Self {
+            config,
+            endpoints,
+            next: AtomicUsize::new(0),
+            latencies: Mutex::new(VecDeque::with_capacity(LATENCY_WINDOW)),
+        }
+    }
+
+    fn latencies(&self) -> std::sync::MutexGuard<'_, VecDeque<Duration>> {
+        self.latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns how long a read waits before it is hedged.
+    pub(crate) fn delay(&self) -> Duration {
+        let latencies = self.latencies();
+        if latencies.len() < MIN_SAMPLES {
+            return self.config.initial_delay.max(self.config.min_delay);
+        }
+
+        let mut sorted = latencies.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        let index = ((sorted.len() - 1) as f64 * self.config.percentile).round() as usize;
+        sorted[index].max(self.config.min_delay)
+    }
+
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies();
+        if latencies.len() == LATENCY_WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// Sends the read to an endpoint, then to the next endpoints whenever the delay elapses without a response.
+    ///
+    /// The first successful response wins, and a request which failed to reach the member is hedged to
+    /// the next endpoint at once. Any other error is returned as is, since another member would fail the same way.
+    /// An error is also returned once every request sent has failed, and no endpoint is left to hedge to.
+    pub(crate) async fn run<T, F, Fut>(
+        &self,
+        method: &'static str,
+        call: F,
+    ) -> std::result::Result<T, Status>
+    where
+        F: Fn(C) -> Fut,
+        Fut: Future<Output = std::result::Result<T, Status>>,
+    {
+        let n = self.endpoints.len();
+        let first = self.next.fetch_add(1, Ordering::Relaxed);
+        let attempts = n.min(self.config.max_hedges + 1);
+
+        let send = |i: usize| {
+            let fut = call(self.endpoints[(first + i) % n].clone());
+            async move {
+                let start = Instant::now();
+                (fut.await, start.elapsed())
+            }
+        };
+
+        let mut pending = FuturesUnordered::new();
+        pending.push(send(0));
+        let mut sent = 1;
+
+        loop {
+            let next = pending.next();
+            let resp = if sent < attempts {
+                match future::select(next, Box::pin(tokio::time::sleep(self.delay()))).await {
+                    Either::Left((resp, _)) => resp,
+                    Either::Right(_) => {
+                        metrics::hedged(method);
+                        pending.push(send(sent));
+                        sent += 1;
+                        continue;
+                    }
+                }
+            } else {
+                next.await
+            };
+
+            match resp {
+                Some((Ok(resp), latency)) => {
+                    self.record(latency);
+                    return Ok(resp);
+                }
+                Some((Err(status), _)) if !should_hedge(&status) => return Err(status),
+                Some((Err(_), _)) if sent < attempts => {
+                    metrics::hedged(method);
+                    pending.push(send(sent));
+                    sent += 1;
+                }
+                Some((Err(status), _)) if pending.is_empty() => return Err(status),
+                Some((Err(_), _)) => {}
+                None => unreachable!("a request is always pending"),
+            }
+        }
+    }
+}
+
+/// Whether the request failed to reach the member, or the member failed to answer it,
+/// rather than being rejected, so another member may answer it.
+///
+/// Transport and connection errors surface as `Unavailable` or `Unknown`.
+fn should_hedge(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::DeadlineExceeded | Code::Unknown
+    )
+}
@@ -0,0 +1,183 @@
+//! Informers, which notify handlers of the changes to a prefix, like the informers of Kubernetes.
+
+mod queue;
+
+pub use queue::WorkQueue;
+
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::StreamExt;
+use tokio::sync::watch;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+use crate::kv::KeyValue;
+use crate::reflector::Reflector;
+use crate::watch::{Event, EventType};
+use crate::{Error, Result};
+
+type KeyValueHandler = Box<dyn Fn(&KeyValue) + Send + Sync>;
+type UpdateHandler = Box<dyn Fn(&KeyValue, &KeyValue) + Send + Sync>;
+
+#[derive(Default)]
+struct Handlers {
+    add: Vec<KeyValueHandler>,
+    update: Vec<UpdateHandler>,
+    delete: Vec<KeyValueHandler>,
+}
+
+impl Handlers {
+    fn add(&self, kv: &KeyValue) {
+        self.add.iter().for_each(|handler| handler(kv));
+    }
+
+    fn update(&self, old: &KeyValue, new: &KeyValue) {
+        self.update.iter().for_each(|handler| handler(old, new));
+    }
+
+    fn delete(&self, kv: &KeyValue) {
+        self.delete.iter().for_each(|handler| handler(kv));
+    }
+
+    fn dispatch(&self, event: Event) {
+        match (event.event_type, event.prev_kv) {
+            (EventType::Put, None) => self.add(&event.kv),
+            (EventType::Put, Some(old)) => self.update(&old, &event.kv),
+            (EventType::Delete, prev_kv) => self.delete(prev_kv.as_ref().unwrap_or(&event.kv)),
+        }
+    }
+}
+
+/// Informer calls handlers for the keys of a [`Reflector`], as they are added, updated and deleted.
+///
+/// Once started, the keys in the cache are added first, then the changes of the cache are dispatched in order.
+/// Handlers are called one at a time on a background task, so slow work should be handed over to
+/// a [`WorkQueue`].
+pub struct Informer {
+    reflector: Reflector,
+    handlers: Handlers,
+    resync_period: Option<Duration>,
+}
+
+impl Informer {
+    /// Creates an informer of the keys of the reflector, without handlers.
+    pub fn new(reflector: Reflector) -> Self {
+        Self {
+            reflector,
+            handlers: Handlers::default(),
+            resync_period: None,
+        }
+    }
+
+    /// Adds a handler of keys which are created, or are in the cache when the informer starts.
+    pub fn on_add<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&KeyValue) + Send + Sync + 'static,
+    {
+        self.handlers.add.push(Box::new(handler));
+        self
+    }
+
+    /// Adds a handler of keys which are updated, called with the old and the new key value.
+    pub fn on_update<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&KeyValue, &KeyValue) + Send + Sync + 'static,
+    {
+        self.handlers.update.push(Box::new(handler));
+        self
+    }
+
+    /// Adds a handler of keys which are deleted, called with the last key value of the key.
+    pub fn on_delete<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&KeyValue) + Send + Sync + 'static,
+    {
+        self.handlers.delete.push(Box::new(handler));
+        self
+    }
+
+    /// Calls the update handlers for every key periodically, with the same key value as old and new,
+    /// so work which failed or was missed is done again.
+    pub fn resync_period(mut self, period: Duration) -> Self {
+        self.resync_period = Some(period);
+        self
+    }
+
+    /// Starts dispatching to the handlers, until the returned handle is dropped.
+    pub fn start(self) -> InformerHandle {
+        let Self {
+            reflector,
+            handlers,
+            resync_period,
+        } = self;
+        let (tx, synced) = watch::channel(false);
+        let (snapshot, mut changes) = reflector.subscribe();
+
+        tokio::spawn(async move {
+            let dispatch = async {
+                let mut kvs = snapshot.kvs;
+                kvs.values().for_each(|kv| handlers.add(kv));
+                tx.send_replace(true);
+
+                let mut resync = resync_period.map(|period| {
+                    let mut interval = interval_at(Instant::now() + period, period);
+                    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    interval
+                });
+                loop {
+                    let event = match resync.as_mut() {
+                        Some(resync) => {
+                            match future::select(changes.next(), Box::pin(resync.tick())).await {
+                                Either::Left((event, _)) => event,
+                                Either::Right(_) => {
+                                    kvs.values().for_each(|kv| handlers.update(kv, kv));
+                                    continue;
+                                }
+                            }
+                        }
+                        None => changes.next().await,
+                    };
+                    let Some(event) = event else {
+                        return;
+                    };
+
+                    match event.event_type {
+                        EventType::Put => kvs.insert(event.kv.key.clone(), event.kv.clone()),
+                        EventType::Delete => kvs.remove(&event.kv.key),
+                    };
+                    handlers.dispatch(event);
+                }
+            };
+            future::select(Box::pin(tx.closed()), Box::pin(dispatch)).await;
+        });
+
+        InformerHandle { reflector, synced }
+    }
+}
+
+/// A running [`Informer`], which stops when dropped.
+pub struct InformerHandle {
+    reflector: Reflector,
+    synced: watch::Receiver<bool>,
+}
+
+impl InformerHandle {
+    /// Returns whether the keys in the cache at start have been passed to the add handlers.
+    pub fn has_synced(&self) -> bool {
+        *self.synced.borrow()
+    }
+
+    /// Waits until the keys in the cache at start have been passed to the add handlers.
+    pub async fn wait_for_sync(&self) -> Result<()> {
+        let mut synced = self.synced.clone();
+        while !*synced.borrow_and_update() {
+            synced.changed().await.map_err(|_| Error::ChannelClosed)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the reflector of the informer, to read the cached keys.
+    pub fn reflector(&self) -> &Reflector {
+        &self.reflector
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::pin::pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Vec<u8>>,
+    /// Keys which are waiting to be processed, whether queued or in processing.
+    dirty: HashSet<Vec<u8>>,
+    processing: HashSet<Vec<u8>>,
+    failures: HashMap<Vec<u8>, u32>,
+    shutting_down: bool,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    notify: Notify,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+/// WorkQueue holds the keys which need to be processed, such as by the handlers of an
+/// [`Informer`](crate::Informer), for workers to process them.
+///
+/// A key is queued once however many times it is added before being processed, and a key is never processed
+/// by two workers at once: a key added while being processed is queued again when it is [`done`](Self::done).
+/// Failed keys are retried with [`add_rate_limited`](Self::add_rate_limited), which delays them
+/// exponentially until they are [`forgotten`](Self::forget).
+///
+/// ```no_run
+/// # async fn reconcile(key: &[u8]) -> etcd_rs::Result<()> { Ok(()) }
+/// # async fn worker(queue: etcd_rs::WorkQueue) {
+/// while let Some(key) = queue.get().await {
+///     match reconcile(&key).await {
+///         Ok(()) => queue.forget(&key),
+///         Err(_) => queue.add_rate_limited(key.clone()),
+///     }
+///     queue.done(&key);
+/// }
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct WorkQueue {
+    inner: Arc<Inner>,
+}
+
+impl Default for WorkQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkQueue {
+    /// Creates a queue whose retries are delayed from 5 milliseconds, doubled up to 60 seconds.
+    pub fn new() -> Self {
+        Self::with_backoff(Duration::from_millis(5), Duration::from_secs(60))
+    }
+
+    /// Creates a queue whose retries are delayed from the base delay, doubled up to the max delay.
+    pub fn with_backoff(base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State::default()),
+                notify: Notify::new(),
+                base_delay,
+                max_delay,
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds the key, unless it is already waiting to be processed or the queue is shutting down.
+    pub fn add(&self, key: impl Into<Vec<u8>>) {
+        let key = key.into();
+        let mut state = self.state();
+        if state.shutting_down || state.dirty.contains(&key) {
+            return;
+        }
+
+        state.dirty.insert(key.clone());
+        if !state.processing.contains(&key) {
+            state.queue.push_back(key);
+            self.inner.notify.notify_one();
+        }
+    }
+
+    /// Adds the key after the delay.
+    pub fn add_after(&self, key: impl Into<Vec<u8>>, delay: Duration) {
+        let key = key.into();
+        if delay.is_zero() {
+            return self.add(key);
+        }
+
+        let queue = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            queue.add(key);
+        });
+    }
+
+    /// Adds the key after a delay, which doubles every time the key is retried until it is forgotten.
+    pub fn add_rate_limited(&self, key: impl Into<Vec<u8>>) {
+        let key = key.into();
+        let failures = {
+            let mut state = self.state();
+            let failures = state.failures.entry(key.clone()).or_default();
+            *failures += 1;
+            *failures
+        };
+
+        let delay = self
+            .inner
+            .base_delay
+            .checked_mul(2u32.saturating_pow(failures - 1))
+            .unwrap_or(self.inner.max_delay)
+            .min(self.inner.max_delay);
+        self.add_after(key, delay);
+    }
+
+    /// Resets the retries of the key, such as once it has been processed successfully.
+    pub fn forget(&self, key: impl AsRef<[u8]>) {
+        self.state().failures.remove(key.as_ref());
+    }
+
+    /// Gets how many times the key has been retried since it was last forgotten.
+    pub fn retries(&self, key: impl AsRef<[u8]>) -> u32 {
+        self.state()
+            .failures
+            .get(key.as_ref())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Waits for the next key to process, which must be marked [`done`](Self::done) afterwards.
+    ///
+    /// Returns `None` once the queue is shut down and drained.
+    pub async fn get(&self) -> Option<Vec<u8>> {
+        loop {
+            // registered before checking the queue, so keys added in between wake it up
+            let mut notified = pin!(self.inner.notify.notified());
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.state();
+                if let Some(key) = state.queue.pop_front() {
+                    state.dirty.remove(&key);
+                    state.processing.insert(key.clone());
+                    return Some(key);
+                }
+                if state.shutting_down {
+                    return None;
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Marks the key as processed, and queues it again if it was added while being processed.
+    pub fn done(&self, key: impl AsRef<[u8]>) {
+        let key = key.as_ref();
+        let mut state = self.state();
+        state.processing.remove(key);
+        if state.dirty.contains(key) {
+            state.queue.push_back(key.to_vec());
+            self.inner.notify.notify_one();
+        }
+    }
+
+    /// Gets the number of keys queued to be processed.
+    pub fn len(&self) -> usize {
+        self.state().queue.len()
+    }
+
+    /// Whether no key is queued to be processed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stops accepting keys, and lets the workers return once the queued keys are processed.
+    pub fn shut_down(&self) {
+        self.state().shutting_down = true;
+        self.inner.notify.notify_waiters();
+    }
+}
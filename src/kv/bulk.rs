@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use futures::stream::{self, StreamExt};
+
+use super::{DeleteRequest, KeyRange, KeyValueOp, PutRequest, RequestLimits, TxnOp, TxnRequest};
+use crate::proto::etcdserverpb;
+use crate::proto::etcdserverpb::request_op::Request;
+use crate::{ResponseHeader, Result};
+
+/// etcd wraps every request into an internal raft request, which adds a header to the payload.
+const RAFT_REQUEST_OVERHEAD: usize = 1024;
+
+/// Field tag of `TxnRequest.success`.
+const TXN_SUCCESS_TAG: u32 = 2;
+
+/// BulkWriter packs a large number of puts and deletes into transactions
+/// which stay under the limits of etcd server, and runs them concurrently.
+///
+/// Operations on the same key are coalesced, only the last one is written.
+pub struct BulkWriter<'a, C> {
+    client: &'a C,
+    limits: RequestLimits,
+    concurrency: usize,
+    atomic: bool,
+    ops: Vec<Option<etcdserverpb::RequestOp>>,
+    index: HashMap<Vec<u8>, usize>,
+}
+
+impl<'a, C> BulkWriter<'a, C>
+where
+    C: KeyValueOp + Sync,
+{
+    /// Creates a new BulkWriter which writes through the specified client, within the limits of the client.
+    pub fn new(client: &'a C) -> Self {
+        Self {
+            client,
+            limits: client.request_limits(),
+            concurrency: 4,
+            atomic: false,
+            ops: vec![],
+            index: HashMap::new(),
+        }
+    }
+
+    /// Sets the limits used for splitting operations into transactions.
+    pub fn limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the maximum number of transactions in flight.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// When set, all operations are written in a single transaction or none of them are.
+    /// Committing fails without writing anything if the operations do not fit in one transaction.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Adds a put operation.
+    pub fn put<R>(&mut self, req: R)
+    where
+        R: Into<PutRequest>,
+    {
+        self.push(TxnOp::Put(req.into()));
+    }
+
+    /// Adds a delete operation of the specified key.
+    pub fn delete<K>(&mut self, key: K)
+    where
+        K: Into<Vec<u8>>,
+    {
+        self.push(TxnOp::Delete(DeleteRequest::new(KeyRange::key(key))));
+    }
+
+    /// Returns the number of operations to be written.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if there is no operation to be written.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn push(&mut self, op: TxnOp) {
+        let op: etcdserverpb::RequestOp = op.into();
+        let key = match &op.request {
+            Some(Request::RequestPut(req)) => req.key.clone(),
+            Some(Request::RequestDeleteRange(req)) => req.key.clone(),
+            _ => unreachable!("bulk writer only accepts put and delete"),
+        };
+
+        if let Some(i) = self.index.insert(key, self.ops.len()) {
+            self.ops[i] = None;
+        }
+        self.ops.push(Some(op));
+    }
+
+    /// Writes all operations.
+    ///
+    /// # Errors
+    /// In atomic mode, will returns `Err` if the operations do not fit in one transaction or the transaction failed.
+    /// Otherwise failures are reported per batch in [`BulkWriteResponse`].
+    pub async fn commit(self) -> Result<BulkWriteResponse> {
+        let ops: Vec<_> = self.ops.into_iter().flatten().collect();
+        if ops.is_empty() {
+            return Ok(BulkWriteResponse { batches: vec![] });
+        }
+
+        if self.atomic {
+            let len = ops.len();
+            let txn = ops
+                .into_iter()
+                .fold(TxnRequest::new(), TxnRequest::and_then_raw);
+            txn.check_limits(&RequestLimits {
+                max_request_bytes: self
+                    .limits
+                    .max_request_bytes
+                    .saturating_sub(RAFT_REQUEST_OVERHEAD),
+                ..self.limits
+            })?;
+            let resp = self.client.txn(txn).await?;
+
+            return Ok(BulkWriteResponse {
+                batches: vec![BulkBatch {
+                    ops: len,
+                    result: Ok(resp.header),
+                }],
+            });
+        }
+
+        let client = self.client;
+        let batches = split(ops, &self.limits);
+        let mut batches: Vec<_> = stream::iter(batches.into_iter().enumerate())
+            .map(|(i, ops)| async move {
+                let len = ops.len();
+                let txn = ops
+                    .into_iter()
+                    .fold(TxnRequest::new(), TxnRequest::and_then_raw);
+                let result = client.txn(txn).await.map(|resp| resp.header);
+                (i, BulkBatch { ops: len, result })
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        batches.sort_by_key(|(i, _)| *i);
+
+        Ok(BulkWriteResponse {
+            batches: batches.into_iter().map(|(_, batch)| batch).collect(),
+        })
+    }
+}
+
+fn split(
+    ops: Vec<etcdserverpb::RequestOp>,
+    limits: &RequestLimits,
+) -> Vec<Vec<etcdserverpb::RequestOp>> {
+    let max_bytes = limits
+        .max_request_bytes
+        .saturating_sub(RAFT_REQUEST_OVERHEAD);
+    let max_ops = limits.max_txn_ops.max(1);
+
+    let mut batches = vec![];
+    let mut batch = vec![];
+    let mut size = 0;
+
+    for op in ops {
+        let op_size = prost::encoding::message::encoded_len(TXN_SUCCESS_TAG, &op);
+        if !batch.is_empty() && (batch.len() >= max_ops || size + op_size > max_bytes) {
+            batches.push(std::mem::take(&mut batch));
+            size = 0;
+        }
+        size += op_size;
+        batch.push(op);
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    batches
+}
+
+/// Result of a transaction written by [`BulkWriter`].
+#[derive(Debug)]
+pub struct BulkBatch {
+    /// Number of operations in the transaction.
+    pub ops: usize,
+    pub result: Result<ResponseHeader>,
+}
+
+impl BulkBatch {
+    /// Returns the revision of the transaction if it succeeded.
+    pub fn revision(&self) -> Option<i64> {
+        self.result.as_ref().ok().map(ResponseHeader::revision)
+    }
+}
+
+#[derive(Debug)]
+pub struct BulkWriteResponse {
+    /// Results of transactions, in the order operations were added.
+    pub batches: Vec<BulkBatch>,
+}
+
+impl BulkWriteResponse {
+    /// Returns `true` if all transactions succeeded.
+    pub fn is_success(&self) -> bool {
+        self.batches.iter().all(|batch| batch.result.is_ok())
+    }
+
+    /// Returns the failed transactions.
+    pub fn failures(&self) -> impl Iterator<Item = &BulkBatch> {
+        self.batches.iter().filter(|batch| batch.result.is_err())
+    }
+
+    /// Returns the highest revision of the succeeded transactions.
+    pub fn revision(&self) -> Option<i64> {
+        self.batches.iter().filter_map(BulkBatch::revision).max()
+    }
+}
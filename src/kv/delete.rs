@@ -1,6 +1,8 @@
-use super::{KeyRange, KeyValue};
+use prost::Message;
+
+use super::{KeyRange, KeyValue, RequestLimits};
 use crate::proto::etcdserverpb;
-use crate::ResponseHeader;
+use crate::{ResponseHeader, Result};
 
 #[derive(Debug)]
 pub struct DeleteRequest {
@@ -28,6 +30,11 @@ impl DeleteRequest {
         self.proto.prev_kv = prev_kv;
         self
     }
+
+    /// Checks the request against the limits of etcd server.
+    pub(crate) fn check_limits(&self, limits: &RequestLimits) -> Result<()> {
+        limits.check_request_bytes(self.proto.encoded_len())
+    }
 }
 
 impl<T> From<T> for DeleteRequest
@@ -45,6 +52,12 @@ impl From<DeleteRequest> for etcdserverpb::DeleteRangeRequest {
     }
 }
 
+impl From<etcdserverpb::DeleteRangeRequest> for DeleteRequest {
+    fn from(proto: etcdserverpb::DeleteRangeRequest) -> Self {
+        Self { proto }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DeleteResponse {
     pub header: ResponseHeader,
@@ -1,14 +1,19 @@
+mod bulk;
 mod compact;
 mod delete;
 mod put;
 mod range;
 mod txn;
 
+pub use bulk::{BulkBatch, BulkWriteResponse, BulkWriter};
 pub use compact::{CompactRequest, CompactResponse};
 pub use delete::{DeleteRequest, DeleteResponse};
 pub use put::{PutRequest, PutResponse};
-pub use range::{RangeRequest, RangeResponse};
-pub use txn::{TxnCmp, TxnOp, TxnOpResponse, TxnRequest, TxnResponse};
+pub use range::{RangeRequest, RangeResponse, ReadConsistency};
+pub use txn::{
+    Compare, TxnCmp, TxnOp, TxnOpHandle, TxnOpRequest, TxnOpResponse, TxnOpResult, TxnRequest,
+    TxnResponse,
+};
 
 use std::ops::Range;
 
@@ -16,7 +21,7 @@ use async_trait::async_trait;
 
 use crate::lease::LeaseId;
 use crate::proto::mvccpb;
-use crate::Result;
+use crate::{Error, Result};
 
 #[async_trait]
 pub trait KeyValueOp {
@@ -55,6 +60,52 @@ pub trait KeyValueOp {
     async fn compact<R>(&self, req: R) -> Result<CompactResponse>
     where
         R: Into<CompactRequest> + Send;
+
+    /// Reads linearizably regardless of the configured consistency, such as when a serializable read is stale.
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send;
+
+    /// Gets the limits requests are checked against, the defaults of etcd server unless configured.
+    fn request_limits(&self) -> RequestLimits {
+        RequestLimits::default()
+    }
+}
+
+/// Limits of requests which are enforced by etcd server.
+///
+/// They should be kept in sync with `--max-txn-ops` and `--max-request-bytes` of the server,
+/// so that oversized requests are rejected before sending.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestLimits {
+    pub max_txn_ops: usize,
+    pub max_request_bytes: usize,
+}
+
+impl RequestLimits {
+    /// Default `--max-txn-ops` of etcd server.
+    pub const DEFAULT_MAX_TXN_OPS: usize = 128;
+    /// Default `--max-request-bytes` of etcd server, 1.5 MiB.
+    pub const DEFAULT_MAX_REQUEST_BYTES: usize = 1536 * 1024;
+
+    pub(crate) fn check_request_bytes(&self, size: usize) -> Result<()> {
+        if size > self.max_request_bytes {
+            return Err(Error::RequestTooLarge {
+                size,
+                limit: self.max_request_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_txn_ops: Self::DEFAULT_MAX_TXN_OPS,
+            max_request_bytes: Self::DEFAULT_MAX_REQUEST_BYTES,
+        }
+    }
 }
 
 /// Key-Value pair.
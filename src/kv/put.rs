@@ -1,7 +1,9 @@
-use super::KeyValue;
+use prost::Message;
+
+use super::{KeyValue, RequestLimits};
 use crate::lease::LeaseId;
 use crate::proto::etcdserverpb;
-use crate::ResponseHeader;
+use crate::{ResponseHeader, Result};
 
 #[derive(Debug)]
 pub struct PutRequest {
@@ -51,6 +53,11 @@ impl PutRequest {
         self.proto.ignore_lease = true;
         self
     }
+
+    /// Checks the request against the limits of etcd server.
+    pub(crate) fn check_limits(&self, limits: &RequestLimits) -> Result<()> {
+        limits.check_request_bytes(self.proto.encoded_len())
+    }
 }
 
 impl From<PutRequest> for etcdserverpb::PutRequest {
@@ -59,6 +66,12 @@ impl From<PutRequest> for etcdserverpb::PutRequest {
     }
 }
 
+impl From<etcdserverpb::PutRequest> for PutRequest {
+    fn from(proto: etcdserverpb::PutRequest) -> Self {
+        Self { proto }
+    }
+}
+
 impl<K, V> From<(K, V)> for PutRequest
 where
     K: Into<Vec<u8>>,
@@ -79,7 +92,7 @@ impl From<etcdserverpb::PutResponse> for PutResponse {
     fn from(proto: etcdserverpb::PutResponse) -> Self {
         Self {
             header: From::from(proto.header.expect("must fetch header")),
-            prev_kv: From::from(proto.prev_kv.unwrap_or(Default::default())),
+            prev_kv: From::from(proto.prev_kv.unwrap_or_default()),
         }
     }
 }
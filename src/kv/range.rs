@@ -69,6 +69,12 @@ impl From<RangeRequest> for etcdserverpb::RangeRequest {
     }
 }
 
+impl From<etcdserverpb::RangeRequest> for RangeRequest {
+    fn from(proto: etcdserverpb::RangeRequest) -> Self {
+        Self { proto }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SortOrder {
     Ascending,
@@ -91,12 +97,26 @@ impl From<SortOrder> for i32 {
     }
 }
 
+/// Consistency of reads, see [`CallOptions::read_consistency`](crate::CallOptions::read_consistency).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadConsistency {
+    /// Reads go through the leader and reflect every write committed before, they fail without a leader.
+    #[default]
+    Linearizable,
+    /// Reads are answered by the member locally, which may be stale.
+    Serializable,
+    /// Linearizable reads, which degrade to serializable reads when the cluster reports no leader.
+    LinearizableWithSerializableFallback,
+}
+
 #[derive(Debug, Clone)]
 pub struct RangeResponse {
     pub header: ResponseHeader,
     pub kvs: Vec<KeyValue>,
     pub has_more: bool,
     pub count: u64,
+    /// Consistency the read was served with, either linearizable or serializable.
+    pub consistency: ReadConsistency,
 }
 
 impl From<etcdserverpb::RangeResponse> for RangeResponse {
@@ -106,6 +126,7 @@ impl From<etcdserverpb::RangeResponse> for RangeResponse {
             kvs: proto.kvs.into_iter().map(From::from).collect(),
             has_more: proto.more,
             count: proto.count as u64,
+            consistency: ReadConsistency::Linearizable,
         }
     }
 }
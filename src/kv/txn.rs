@@ -1,10 +1,15 @@
+use std::marker::PhantomData;
+
+use prost::Message;
+
 use super::{
     DeleteRequest, DeleteResponse, KeyRange, PutRequest, PutResponse, RangeRequest, RangeResponse,
+    RequestLimits,
 };
+use crate::lease::LeaseId;
 use crate::proto::etcdserverpb;
-use crate::ResponseHeader;
+use crate::{Error, ResponseHeader, Result};
 use etcdserverpb::compare::{CompareResult, CompareTarget, TargetUnion};
-use etcdserverpb::Compare;
 
 #[derive(Debug)]
 pub struct TxnRequest {
@@ -23,64 +28,52 @@ impl TxnRequest {
         }
     }
 
-    /// Adds a version compare.
-    pub fn when_version(mut self, key_range: KeyRange, cmp: TxnCmp, version: usize) -> Self {
-        let result: CompareResult = cmp.into();
-        self.proto.compare.push(Compare {
-            result: result as i32,
-            target: CompareTarget::Version as i32,
-            key: key_range.key,
-            range_end: key_range.range_end,
-            target_union: Some(TargetUnion::Version(version as i64)),
-        });
+    /// Adds a compare.
+    pub fn when<C>(mut self, cmp: C) -> Self
+    where
+        C: Into<Compare>,
+    {
+        self.proto.compare.push(cmp.into().into());
         self
     }
 
-    /// Adds a create revision compare.
-    pub fn when_create_revision(
-        mut self,
-        key_range: KeyRange,
-        cmp: TxnCmp,
-        revision: usize,
-    ) -> Self {
-        let result: CompareResult = cmp.into();
-        self.proto.compare.push(Compare {
-            result: result as i32,
-            target: CompareTarget::Create as i32,
-            key: key_range.key,
-            range_end: key_range.range_end,
-            target_union: Some(TargetUnion::CreateRevision(revision as i64)),
-        });
+    /// Adds multiple compares.
+    pub fn when_all<I>(mut self, cmps: I) -> Self
+    where
+        I: IntoIterator<Item = Compare>,
+    {
+        self.proto
+            .compare
+            .extend(cmps.into_iter().map(etcdserverpb::Compare::from));
         self
     }
 
+    /// Adds a version compare.
+    pub fn when_version(self, key_range: KeyRange, cmp: TxnCmp, version: i64) -> Self {
+        self.when(Compare::version(key_range, cmp, version))
+    }
+
+    /// Adds a create revision compare.
+    pub fn when_create_revision(self, key_range: KeyRange, cmp: TxnCmp, revision: i64) -> Self {
+        self.when(Compare::create_revision(key_range, cmp, revision))
+    }
+
     /// Adds a mod revision compare.
-    pub fn when_mod_revision(mut self, key_range: KeyRange, cmp: TxnCmp, revision: usize) -> Self {
-        let result: CompareResult = cmp.into();
-        self.proto.compare.push(Compare {
-            result: result as i32,
-            target: CompareTarget::Mod as i32,
-            key: key_range.key,
-            range_end: key_range.range_end,
-            target_union: Some(TargetUnion::ModRevision(revision as i64)),
-        });
-        self
+    pub fn when_mod_revision(self, key_range: KeyRange, cmp: TxnCmp, revision: i64) -> Self {
+        self.when(Compare::mod_revision(key_range, cmp, revision))
     }
 
     /// Adds a value compare.
-    pub fn when_value<V>(mut self, key_range: KeyRange, cmp: TxnCmp, value: V) -> Self
+    pub fn when_value<V>(self, key_range: KeyRange, cmp: TxnCmp, value: V) -> Self
     where
         V: Into<Vec<u8>>,
     {
-        let result: CompareResult = cmp.into();
-        self.proto.compare.push(Compare {
-            result: result as i32,
-            target: CompareTarget::Value as i32,
-            key: key_range.key,
-            range_end: key_range.range_end,
-            target_union: Some(TargetUnion::Value(value.into())),
-        });
-        self
+        self.when(Compare::value(key_range, cmp, value))
+    }
+
+    /// Adds a lease compare.
+    pub fn when_lease(self, key_range: KeyRange, cmp: TxnCmp, lease: LeaseId) -> Self {
+        self.when(Compare::lease(key_range, cmp, lease))
     }
 
     /// If compare success, then execute the specified operations.
@@ -92,6 +85,11 @@ impl TxnRequest {
         self
     }
 
+    pub(crate) fn and_then_raw(mut self, op: etcdserverpb::RequestOp) -> Self {
+        self.proto.success.push(op);
+        self
+    }
+
     /// If compare fail, then execute the specified operations.
     pub fn or_else<O>(mut self, op: O) -> Self
     where
@@ -100,6 +98,55 @@ impl TxnRequest {
         self.proto.failure.push(op.into().into());
         self
     }
+
+    /// If compare success, then execute the specified operation.
+    /// Returns a handle for fetching the typed result from [`TxnResponse::get`].
+    pub fn push_and_then<O>(&mut self, op: O) -> TxnOpHandle<O::Response>
+    where
+        O: TxnOpRequest,
+    {
+        self.proto.success.push(op.into().into());
+        TxnOpHandle::new(true, self.proto.success.len() - 1)
+    }
+
+    /// If compare fail, then execute the specified operation.
+    /// Returns a handle for fetching the typed result from [`TxnResponse::get`].
+    pub fn push_or_else<O>(&mut self, op: O) -> TxnOpHandle<O::Response>
+    where
+        O: TxnOpRequest,
+    {
+        self.proto.failure.push(op.into().into());
+        TxnOpHandle::new(false, self.proto.failure.len() - 1)
+    }
+
+    /// Checks the request against the limits of etcd server.
+    ///
+    /// Nested transactions share the operation budget with their parents, as etcd does.
+    pub(crate) fn check_limits(&self, limits: &RequestLimits) -> Result<()> {
+        check_txn_ops(&self.proto, limits.max_txn_ops, limits.max_txn_ops)?;
+        limits.check_request_bytes(self.proto.encoded_len())
+    }
+}
+
+fn check_txn_ops(txn: &etcdserverpb::TxnRequest, budget: usize, limit: usize) -> Result<()> {
+    use etcdserverpb::request_op::Request;
+
+    let count = txn
+        .compare
+        .len()
+        .max(txn.success.len())
+        .max(txn.failure.len());
+    if count > budget {
+        return Err(Error::TooManyTxnOps { count, limit });
+    }
+
+    for op in txn.success.iter().chain(txn.failure.iter()) {
+        if let Some(Request::RequestTxn(nested)) = &op.request {
+            check_txn_ops(nested, budget - count, limit)?;
+        }
+    }
+
+    Ok(())
 }
 
 impl Default for TxnRequest {
@@ -114,6 +161,12 @@ impl From<TxnRequest> for crate::proto::etcdserverpb::TxnRequest {
     }
 }
 
+impl From<etcdserverpb::TxnRequest> for TxnRequest {
+    fn from(proto: etcdserverpb::TxnRequest) -> Self {
+        Self { proto }
+    }
+}
+
 /// Transaction Operation.
 pub enum TxnOp {
     Range(RangeRequest),
@@ -161,7 +214,193 @@ impl From<TxnRequest> for TxnOp {
     }
 }
 
+/// Request which can be executed as a transaction operation.
+pub trait TxnOpRequest: Into<TxnOp> {
+    type Response: TxnOpResult;
+}
+
+impl TxnOpRequest for RangeRequest {
+    type Response = RangeResponse;
+}
+
+impl TxnOpRequest for PutRequest {
+    type Response = PutResponse;
+}
+
+impl TxnOpRequest for DeleteRequest {
+    type Response = DeleteResponse;
+}
+
+impl TxnOpRequest for TxnRequest {
+    type Response = TxnResponse;
+}
+
+/// Typed result of a transaction operation.
+pub trait TxnOpResult: Sized {
+    fn from_op_response(resp: &TxnOpResponse) -> Option<&Self>;
+}
+
+impl TxnOpResult for RangeResponse {
+    fn from_op_response(resp: &TxnOpResponse) -> Option<&Self> {
+        match resp {
+            TxnOpResponse::Range(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+impl TxnOpResult for PutResponse {
+    fn from_op_response(resp: &TxnOpResponse) -> Option<&Self> {
+        match resp {
+            TxnOpResponse::Put(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+impl TxnOpResult for DeleteResponse {
+    fn from_op_response(resp: &TxnOpResponse) -> Option<&Self> {
+        match resp {
+            TxnOpResponse::Delete(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+impl TxnOpResult for TxnResponse {
+    fn from_op_response(resp: &TxnOpResponse) -> Option<&Self> {
+        match resp {
+            TxnOpResponse::Txn(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+/// Handle of an operation added to a [`TxnRequest`].
+///
+/// It locates the operation's result in the matching [`TxnResponse`].
+/// For a nested transaction, use the handle of the nested operation on the nested response.
+#[derive(Debug)]
+pub struct TxnOpHandle<T> {
+    on_success: bool,
+    index: usize,
+    _response: PhantomData<fn() -> T>,
+}
+
+impl<T> TxnOpHandle<T> {
+    fn new(on_success: bool, index: usize) -> Self {
+        Self {
+            on_success,
+            index,
+            _response: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the operation is executed when compares succeed.
+    pub fn on_success(&self) -> bool {
+        self.on_success
+    }
+
+    /// Returns the position of the operation in its branch.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T> Clone for TxnOpHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TxnOpHandle<T> {}
+
+/// Transaction compare condition.
+///
+/// A `Compare` can be built separately from a [`TxnRequest`], so that the same guard
+/// can be reused across transactions.
+#[derive(Debug, Clone)]
+pub struct Compare {
+    proto: etcdserverpb::Compare,
+}
+
+impl Compare {
+    fn new(key_range: KeyRange, cmp: TxnCmp, target: CompareTarget, union: TargetUnion) -> Self {
+        let result: CompareResult = cmp.into();
+        Self {
+            proto: etcdserverpb::Compare {
+                result: result as i32,
+                target: target as i32,
+                key: key_range.key,
+                range_end: key_range.range_end,
+                target_union: Some(union),
+            },
+        }
+    }
+
+    /// Creates a version compare.
+    pub fn version(key_range: KeyRange, cmp: TxnCmp, version: i64) -> Self {
+        Self::new(
+            key_range,
+            cmp,
+            CompareTarget::Version,
+            TargetUnion::Version(version),
+        )
+    }
+
+    /// Creates a create revision compare.
+    pub fn create_revision(key_range: KeyRange, cmp: TxnCmp, revision: i64) -> Self {
+        Self::new(
+            key_range,
+            cmp,
+            CompareTarget::Create,
+            TargetUnion::CreateRevision(revision),
+        )
+    }
+
+    /// Creates a mod revision compare.
+    pub fn mod_revision(key_range: KeyRange, cmp: TxnCmp, revision: i64) -> Self {
+        Self::new(
+            key_range,
+            cmp,
+            CompareTarget::Mod,
+            TargetUnion::ModRevision(revision),
+        )
+    }
+
+    /// Creates a value compare.
+    pub fn value<V>(key_range: KeyRange, cmp: TxnCmp, value: V) -> Self
+    where
+        V: Into<Vec<u8>>,
+    {
+        Self::new(
+            key_range,
+            cmp,
+            CompareTarget::Value,
+            TargetUnion::Value(value.into()),
+        )
+    }
+
+    /// Creates a lease compare.
+    /// A lease value of 0 matches keys which are not attached to any lease.
+    pub fn lease(key_range: KeyRange, cmp: TxnCmp, lease: LeaseId) -> Self {
+        Self::new(
+            key_range,
+            cmp,
+            CompareTarget::Lease,
+            TargetUnion::Lease(lease),
+        )
+    }
+}
+
+impl From<Compare> for etcdserverpb::Compare {
+    fn from(x: Compare) -> Self {
+        x.proto
+    }
+}
+
 /// Transaction Comparation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TxnCmp {
     Equal,
     NotEqual,
@@ -208,6 +447,27 @@ pub struct TxnResponse {
     pub responses: Vec<TxnOpResponse>,
 }
 
+impl TxnResponse {
+    /// Gets the typed result of the operation located by the handle.
+    ///
+    /// # Errors
+    /// Will returns `Err` if the operation was on the branch which was not executed,
+    /// or the result does not match the operation.
+    pub fn get<T>(&self, handle: &TxnOpHandle<T>) -> Result<&T>
+    where
+        T: TxnOpResult,
+    {
+        if handle.on_success != self.succeeded {
+            return Err(Error::TxnBranchMismatch);
+        }
+
+        self.responses
+            .get(handle.index)
+            .and_then(T::from_op_response)
+            .ok_or(Error::TxnOpMismatch(handle.index))
+    }
+}
+
 impl From<etcdserverpb::TxnResponse> for TxnResponse {
     fn from(proto: etcdserverpb::TxnResponse) -> Self {
         Self {
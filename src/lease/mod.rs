@@ -15,8 +15,10 @@ pub use revoke::{LeaseRevokeRequest, LeaseRevokeResponse};
 pub use time_to_live::{LeaseTimeToLiveRequest, LeaseTimeToLiveResponse};
 
 use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
 use tokio::sync::mpsc::Sender;
-use tonic::Streaming;
+use tonic::Status;
 
 use crate::{Error, Result};
 
@@ -42,19 +44,31 @@ pub trait LeaseOp {
 pub struct LeaseKeepAlive {
     id: LeaseId,
     req_tx: Sender<crate::proto::etcdserverpb::LeaseKeepAliveRequest>,
-    resp_rx: Streaming<crate::proto::etcdserverpb::LeaseKeepAliveResponse>,
+    resp_rx: BoxStream<
+        'static,
+        std::result::Result<crate::proto::etcdserverpb::LeaseKeepAliveResponse, Status>,
+    >,
 }
 
 impl LeaseKeepAlive {
-    pub(crate) fn new(
+    pub(crate) fn new<S>(
         id: LeaseId,
         req_tx: Sender<crate::proto::etcdserverpb::LeaseKeepAliveRequest>,
-        resp_rx: Streaming<crate::proto::etcdserverpb::LeaseKeepAliveResponse>,
-    ) -> Self {
+        resp_rx: S,
+    ) -> Self
+    where
+        S: Stream<
+                Item = std::result::Result<
+                    crate::proto::etcdserverpb::LeaseKeepAliveResponse,
+                    Status,
+                >,
+            > + Send
+            + 'static,
+    {
         Self {
             id,
             req_tx,
-            resp_rx,
+            resp_rx: resp_rx.boxed(),
         }
     }
 
@@ -71,6 +85,10 @@ impl LeaseKeepAlive {
             .await
             .map_err(|_| Error::ChannelClosed)?;
 
-        Ok(self.resp_rx.message().await?.map(|resp| resp.into()))
+        match self.resp_rx.next().await {
+            Some(Ok(resp)) => Ok(Some(resp.into())),
+            Some(Err(e)) => Err(e.into()),
+            None => Ok(None),
+        }
     }
 }
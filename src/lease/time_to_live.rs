@@ -43,6 +43,8 @@ pub struct LeaseTimeToLiveResponse {
     pub header: ResponseHeader,
     pub id: LeaseId,
     pub ttl: i64,
+    /// Keys attached to the lease, when requested with [`LeaseTimeToLiveRequest::with_keys`].
+    pub keys: Vec<Vec<u8>>,
 }
 
 impl From<crate::proto::etcdserverpb::LeaseTimeToLiveResponse> for LeaseTimeToLiveResponse {
@@ -51,6 +53,7 @@ impl From<crate::proto::etcdserverpb::LeaseTimeToLiveResponse> for LeaseTimeToLi
             header: From::from(proto.header.expect("must fetch header")),
             id: proto.id,
             ttl: proto.ttl,
+            keys: proto.keys,
         }
     }
 }
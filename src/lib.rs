@@ -21,33 +21,69 @@ pub use cluster::{
     MemberRemoveRequest, MemberRemoveResponse, MemberUpdateRequest, MemberUpdateResponse,
 };
 pub use kv::{
-    CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValue, KeyValueOp,
-    PutRequest, PutResponse, RangeRequest, RangeResponse, TxnCmp, TxnOp, TxnOpResponse, TxnRequest,
-    TxnResponse,
+    BulkBatch, BulkWriteResponse, BulkWriter, CompactRequest, CompactResponse, Compare,
+    DeleteRequest, DeleteResponse, KeyRange, KeyValue, KeyValueOp, PutRequest, PutResponse,
+    RangeRequest, RangeResponse, ReadConsistency, RequestLimits, TxnCmp, TxnOp, TxnOpHandle,
+    TxnOpRequest, TxnOpResponse, TxnOpResult, TxnRequest, TxnResponse,
 };
 pub use lease::{
     LeaseGrantRequest, LeaseGrantResponse, LeaseId, LeaseKeepAlive, LeaseKeepAliveRequest,
     LeaseKeepAliveResponse, LeaseOp, LeaseRevokeRequest, LeaseRevokeResponse,
     LeaseTimeToLiveRequest, LeaseTimeToLiveResponse,
 };
+pub use namespace::Namespaced;
+pub use reflector::{Reflector, ReflectorSnapshot};
 pub use response_header::ResponseHeader;
+pub use routing::RoutingPolicy;
+pub use session::ConsistentSession;
 pub use watch::{
     Event, EventType, WatchCancelRequest, WatchCanceler, WatchCreateRequest, WatchInbound, WatchOp,
     WatchResponse, WatchStream,
 };
 
-pub use client::{Client, ClientConfig, Endpoint};
+pub use cache::CachedKv;
+pub use client::{CallOptions, Client, ClientConfig, Endpoint, Transport};
+#[cfg(feature = "dns")]
+pub use discovery::SystemResolver;
+pub use discovery::{SrvDiscovery, SrvRecord, SrvResolver};
 pub use error::Error;
+pub use health::{EndpointHealth, HealthCheck, HealthProbe};
+pub use hedge::Hedging;
+pub use informer::{Informer, InformerHandle, WorkQueue};
+#[cfg(feature = "testing")]
+pub use testing::FakeClient;
+#[cfg(feature = "mock-server")]
+pub use testing::MockEtcdServer;
+#[cfg(feature = "tls")]
+pub use tls::TlsFiles;
 
 mod auth;
+mod balance;
+mod cache;
 mod client;
 mod cluster;
+mod config;
+mod discovery;
 mod error;
+mod health;
+mod hedge;
+mod informer;
 mod kv;
 mod lease;
 mod lock;
+mod metrics;
+mod namespace;
 mod proto;
+mod reflector;
 mod response_header;
+mod routing;
+pub mod service;
+mod session;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "tls")]
+mod tls;
+mod trace;
 mod watch;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,178 @@
+//! Client metrics recorded through the [`metrics`](https://docs.rs/metrics) facade, enabled by the `metrics` feature.
+//!
+//! - `etcd_client_requests_total{method}`: requests sent.
+//! - `etcd_client_errors_total{method, code}`: failed requests, by gRPC code.
+//! - `etcd_client_request_duration_seconds{method}`: latency of requests.
+//! - `etcd_client_sent_bytes_total{method}` and `etcd_client_received_bytes_total{method}`: encoded message sizes.
+//! - `etcd_client_active_watches`: watch streams alive.
+//! - `etcd_client_keep_alive_leases`: lease keep-alive streams alive.
+//! - `etcd_client_endpoint_healthy{endpoint}`: whether the last health check of an endpoint passed.
+//! - `etcd_client_hedged_requests_total{method}`: reads sent to another endpoint after the hedging delay.
+//! - `etcd_client_cache_requests_total{result}`: single-key reads of [`CachedKv`](crate::CachedKv), by `hit` or `miss`.
+
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+use futures::Stream;
+
+#[cfg(feature = "metrics")]
+use futures::StreamExt;
+
+pub(crate) const ACTIVE_WATCHES: &str = "etcd_client_active_watches";
+pub(crate) const KEEP_ALIVE_LEASES: &str = "etcd_client_keep_alive_leases";
+
+#[cfg(feature = "metrics")]
+const REQUESTS_TOTAL: &str = "etcd_client_requests_total";
+#[cfg(feature = "metrics")]
+const ERRORS_TOTAL: &str = "etcd_client_errors_total";
+#[cfg(feature = "metrics")]
+const REQUEST_DURATION_SECONDS: &str = "etcd_client_request_duration_seconds";
+#[cfg(feature = "metrics")]
+const SENT_BYTES_TOTAL: &str = "etcd_client_sent_bytes_total";
+#[cfg(feature = "metrics")]
+const RECEIVED_BYTES_TOTAL: &str = "etcd_client_received_bytes_total";
+#[cfg(feature = "metrics")]
+const ENDPOINT_HEALTHY: &str = "etcd_client_endpoint_healthy";
+#[cfg(feature = "metrics")]
+const HEDGED_REQUESTS_TOTAL: &str = "etcd_client_hedged_requests_total";
+#[cfg(feature = "metrics")]
+const CACHE_REQUESTS_TOTAL: &str = "etcd_client_cache_requests_total";
+
+/// Metrics of a single request, it does nothing without the `metrics` feature.
+pub(crate) struct RequestMetrics {
+    #[cfg(feature = "metrics")]
+    method: &'static str,
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+impl RequestMetrics {
+    pub(crate) fn new(method: &'static str) -> Self {
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!(REQUESTS_TOTAL, "method" => method).increment(1);
+
+        Self {
+            #[cfg(feature = "metrics")]
+            method,
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn finish(self, result: std::result::Result<(), &crate::Error>) {
+        #[cfg(feature = "metrics")]
+        {
+            ::metrics::histogram!(REQUEST_DURATION_SECONDS, "method" => self.method)
+                .record(self.start.elapsed().as_secs_f64());
+
+            let code = match result {
+                Ok(()) => return,
+                Err(crate::Error::Response(status)) => format!("{:?}", status.code()),
+                Err(crate::Error::Transport(_)) => "Transport".to_owned(),
+                Err(crate::Error::Timeout) => "Timeout".to_owned(),
+                Err(_) => "Client".to_owned(),
+            };
+            ::metrics::counter!(ERRORS_TOTAL, "method" => self.method, "code" => code).increment(1);
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn sent<M: prost::Message>(method: &'static str, msg: &M) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(SENT_BYTES_TOTAL, "method" => method).increment(msg.encoded_len() as u64);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn received<M: prost::Message>(method: &'static str, msg: &M) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(RECEIVED_BYTES_TOTAL, "method" => method)
+        .increment(msg.encoded_len() as u64);
+}
+
+/// Records the health of the endpoint probed by the health checker.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn endpoint_health(endpoint: &str, healthy: bool) {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!(ENDPOINT_HEALTHY, "endpoint" => endpoint.to_owned()).set(if healthy {
+        1.0
+    } else {
+        0.0
+    });
+}
+
+/// Counts a read hedged to another endpoint.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn hedged(method: &'static str) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(HEDGED_REQUESTS_TOTAL, "method" => method).increment(1);
+}
+
+/// Counts a single-key read of the cache, by whether it was served from the cache.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn cache(hit: bool) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(CACHE_REQUESTS_TOTAL, "result" => if hit { "hit" } else { "miss" })
+        .increment(1);
+}
+
+/// Counts the bytes of messages sent through a request stream.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn sent_stream<S, M>(method: &'static str, stream: S) -> impl Stream<Item = M>
+where
+    S: Stream<Item = M>,
+    M: prost::Message,
+{
+    #[cfg(feature = "metrics")]
+    return stream.inspect(move |msg| sent(method, msg));
+
+    #[cfg(not(feature = "metrics"))]
+    stream
+}
+
+/// Counts the bytes of messages received from a response stream,
+/// and keeps the gauge incremented while the stream is alive.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn received_stream<S, M, E>(
+    method: &'static str,
+    gauge: &'static str,
+    stream: S,
+) -> impl Stream<Item = std::result::Result<M, E>>
+where
+    S: Stream<Item = std::result::Result<M, E>>,
+    M: prost::Message,
+{
+    #[cfg(feature = "metrics")]
+    {
+        let guard = GaugeGuard::new(gauge);
+        stream.inspect(move |msg| {
+            let _ = &guard;
+            if let Ok(msg) = msg {
+                received(method, msg);
+            }
+        })
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    stream
+}
+
+/// Increments a gauge until dropped.
+#[cfg(feature = "metrics")]
+struct GaugeGuard(&'static str);
+
+#[cfg(feature = "metrics")]
+impl GaugeGuard {
+    fn new(name: &'static str) -> Self {
+        ::metrics::gauge!(name).increment(1.0);
+        Self(name)
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        ::metrics::gauge!(self.0).decrement(1.0);
+    }
+}
@@ -0,0 +1,325 @@
+//! Namespace isolates keys of a client by transparently prefixing them.
+//!
+//! Keys of requests are prefixed before sending, and the prefix is stripped from keys of responses and watch events.
+//! [`KeyRange::all`] maps to all keys under the namespace.
+
+use async_trait::async_trait;
+
+use crate::kv::{
+    CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValue, KeyValueOp,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, RequestLimits, TxnOpResponse, TxnRequest,
+    TxnResponse,
+};
+use crate::lease::{
+    LeaseGrantRequest, LeaseGrantResponse, LeaseId, LeaseKeepAlive, LeaseOp, LeaseRevokeRequest,
+    LeaseRevokeResponse, LeaseTimeToLiveRequest, LeaseTimeToLiveResponse,
+};
+use crate::proto::{etcdserverpb, mvccpb};
+use crate::watch::{WatchCanceler, WatchCreateRequest, WatchOp, WatchStream};
+use crate::Result;
+
+/// Namespaced wraps a client and confines all of its keys under a prefix.
+#[derive(Clone)]
+pub struct Namespaced<C> {
+    inner: C,
+    prefix: Vec<u8>,
+}
+
+impl<C> Namespaced<C> {
+    /// Creates a new Namespaced client which prefixes keys with the specified value.
+    pub fn new<P>(inner: C, prefix: P) -> Self
+    where
+        P: Into<Vec<u8>>,
+    {
+        Self {
+            inner,
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Gets the prefix of the namespace.
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Gets the underlying client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Unwraps the underlying client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn prefix_key(&self, key: Vec<u8>) -> Vec<u8> {
+        [self.prefix.as_slice(), key.as_slice()].concat()
+    }
+
+    fn prefix_interval(&self, key: Vec<u8>, range_end: Vec<u8>) -> (Vec<u8>, Vec<u8>) {
+        if key == [0] && range_end == [0] {
+            let range = KeyRange::prefix(self.prefix.clone());
+            return (range.key, range.range_end);
+        }
+
+        let range_end = if range_end == [0] {
+            // the edge of the keyspace is the end of the namespace
+            KeyRange::prefix(self.prefix.clone()).range_end
+        } else if range_end.is_empty() {
+            range_end
+        } else {
+            self.prefix_key(range_end)
+        };
+
+        (self.prefix_key(key), range_end)
+    }
+
+    fn prefix_range(&self, mut req: etcdserverpb::RangeRequest) -> etcdserverpb::RangeRequest {
+        (req.key, req.range_end) = self.prefix_interval(req.key, req.range_end);
+        req
+    }
+
+    fn prefix_put(&self, mut req: etcdserverpb::PutRequest) -> etcdserverpb::PutRequest {
+        req.key = self.prefix_key(req.key);
+        req
+    }
+
+    fn prefix_delete(
+        &self,
+        mut req: etcdserverpb::DeleteRangeRequest,
+    ) -> etcdserverpb::DeleteRangeRequest {
+        (req.key, req.range_end) = self.prefix_interval(req.key, req.range_end);
+        req
+    }
+
+    fn prefix_txn(&self, mut req: etcdserverpb::TxnRequest) -> etcdserverpb::TxnRequest {
+        use etcdserverpb::request_op::Request;
+
+        for cmp in req.compare.iter_mut() {
+            let key = std::mem::take(&mut cmp.key);
+            let range_end = std::mem::take(&mut cmp.range_end);
+            (cmp.key, cmp.range_end) = self.prefix_interval(key, range_end);
+        }
+
+        for op in req.success.iter_mut().chain(req.failure.iter_mut()) {
+            op.request = op.request.take().map(|req| match req {
+                Request::RequestRange(req) => Request::RequestRange(self.prefix_range(req)),
+                Request::RequestPut(req) => Request::RequestPut(self.prefix_put(req)),
+                Request::RequestDeleteRange(req) => {
+                    Request::RequestDeleteRange(self.prefix_delete(req))
+                }
+                Request::RequestTxn(req) => Request::RequestTxn(self.prefix_txn(req)),
+            });
+        }
+
+        req
+    }
+
+    fn strip_key(&self, key: &mut Vec<u8>) {
+        if key.starts_with(&self.prefix) {
+            key.drain(..self.prefix.len());
+        }
+    }
+
+    fn strip_kv(&self, kv: &mut KeyValue) {
+        self.strip_key(&mut kv.key);
+    }
+
+    fn strip_txn(&self, resp: &mut TxnResponse) {
+        for op in resp.responses.iter_mut() {
+            match op {
+                TxnOpResponse::Range(resp) => resp.kvs.iter_mut().for_each(|kv| self.strip_kv(kv)),
+                TxnOpResponse::Put(resp) => self.strip_kv(&mut resp.prev_kv),
+                TxnOpResponse::Delete(resp) => {
+                    resp.prev_kvs.iter_mut().for_each(|kv| self.strip_kv(kv))
+                }
+                TxnOpResponse::Txn(resp) => self.strip_txn(resp),
+            }
+        }
+    }
+}
+
+fn strip_watch_response(
+    prefix: &[u8],
+    mut resp: etcdserverpb::WatchResponse,
+) -> etcdserverpb::WatchResponse {
+    let strip = |kv: &mut mvccpb::KeyValue| {
+        if kv.key.starts_with(prefix) {
+            kv.key.drain(..prefix.len());
+        }
+    };
+
+    for event in resp.events.iter_mut() {
+        event.kv.iter_mut().for_each(strip);
+        event.prev_kv.iter_mut().for_each(strip);
+    }
+
+    resp
+}
+
+#[async_trait]
+impl<C> KeyValueOp for Namespaced<C>
+where
+    C: KeyValueOp + Send + Sync,
+{
+    async fn put<R>(&self, req: R) -> Result<PutResponse>
+    where
+        R: Into<PutRequest> + Send,
+    {
+        let req = self.prefix_put(req.into().into());
+        let mut resp = self.inner.put(PutRequest::from(req)).await?;
+        self.strip_kv(&mut resp.prev_kv);
+
+        Ok(resp)
+    }
+
+    async fn get<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        let req = self.prefix_range(req.into().into());
+        let mut resp = self.inner.get(RangeRequest::from(req)).await?;
+        resp.kvs.iter_mut().for_each(|kv| self.strip_kv(kv));
+
+        Ok(resp)
+    }
+
+    async fn get_all(&self) -> Result<RangeResponse> {
+        self.get(KeyRange::all()).await
+    }
+
+    async fn get_by_prefix<K>(&self, p: K) -> Result<RangeResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.get(KeyRange::prefix(p)).await
+    }
+
+    async fn get_range<F, E>(&self, from: F, end: E) -> Result<RangeResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.get(KeyRange::range(from, end)).await
+    }
+
+    async fn delete<R>(&self, req: R) -> Result<DeleteResponse>
+    where
+        R: Into<DeleteRequest> + Send,
+    {
+        let req = self.prefix_delete(req.into().into());
+        let mut resp = self.inner.delete(DeleteRequest::from(req)).await?;
+        resp.prev_kvs.iter_mut().for_each(|kv| self.strip_kv(kv));
+
+        Ok(resp)
+    }
+
+    async fn delete_all(&self) -> Result<DeleteResponse> {
+        self.delete(KeyRange::all()).await
+    }
+
+    async fn delete_by_prefix<K>(&self, p: K) -> Result<DeleteResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::prefix(p)).await
+    }
+
+    async fn delete_range<F, E>(&self, from: F, end: E) -> Result<DeleteResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::range(from, end)).await
+    }
+
+    async fn txn<R>(&self, req: R) -> Result<TxnResponse>
+    where
+        R: Into<TxnRequest> + Send,
+    {
+        let req = self.prefix_txn(req.into().into());
+        let mut resp = self.inner.txn(TxnRequest::from(req)).await?;
+        self.strip_txn(&mut resp);
+
+        Ok(resp)
+    }
+
+    async fn compact<R>(&self, req: R) -> Result<CompactResponse>
+    where
+        R: Into<CompactRequest> + Send,
+    {
+        self.inner.compact(req).await
+    }
+
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        let req = self.prefix_range(req.into().into());
+        let mut resp = self.inner.get_linearizable(RangeRequest::from(req)).await?;
+        resp.kvs.iter_mut().for_each(|kv| self.strip_kv(kv));
+
+        Ok(resp)
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        self.inner.request_limits()
+    }
+}
+
+#[async_trait]
+impl<C> WatchOp for Namespaced<C>
+where
+    C: WatchOp + Send + Sync,
+{
+    async fn watch<R>(&self, req: R) -> Result<(WatchStream, WatchCanceler)>
+    where
+        R: Into<WatchCreateRequest> + Send,
+    {
+        let mut req: etcdserverpb::WatchCreateRequest = req.into().into();
+        (req.key, req.range_end) = self.prefix_interval(req.key, req.range_end);
+
+        let (stream, canceler) = self.inner.watch(WatchCreateRequest::from(req)).await?;
+
+        let prefix = self.prefix.clone();
+        let stream = stream.map_response(move |resp| strip_watch_response(&prefix, resp));
+
+        Ok((stream, canceler))
+    }
+}
+
+#[async_trait]
+impl<C> LeaseOp for Namespaced<C>
+where
+    C: LeaseOp + Send + Sync,
+{
+    async fn grant_lease<R>(&self, req: R) -> Result<LeaseGrantResponse>
+    where
+        R: Into<LeaseGrantRequest> + Send,
+    {
+        self.inner.grant_lease(req).await
+    }
+
+    async fn revoke<R>(&self, req: R) -> Result<LeaseRevokeResponse>
+    where
+        R: Into<LeaseRevokeRequest> + Send,
+    {
+        self.inner.revoke(req).await
+    }
+
+    async fn keep_alive_for(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive> {
+        self.inner.keep_alive_for(lease_id).await
+    }
+
+    async fn time_to_live<R>(&self, req: R) -> Result<LeaseTimeToLiveResponse>
+    where
+        R: Into<LeaseTimeToLiveRequest> + Send,
+    {
+        let mut resp = self.inner.time_to_live(req).await?;
+        // keys of other namespaces attached to the lease are left out
+        resp.keys.retain(|key| key.starts_with(&self.prefix));
+        resp.keys.iter_mut().for_each(|key| self.strip_key(key));
+
+        Ok(resp)
+    }
+}
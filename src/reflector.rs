@@ -0,0 +1,355 @@
+//! A local cache of a prefix, which is kept in sync by list-then-watch.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future;
+use futures::stream::{BoxStream, StreamExt};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::watch;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::kv::{KeyRange, KeyValue, KeyValueOp};
+use crate::watch::{
+    Event, EventType, WatchCanceler, WatchCreateRequest, WatchInbound, WatchOp, WatchStream,
+};
+use crate::{Error, Result};
+
+/// Delay of the first retry after a failed list or watch, doubled on every failure in a row.
+const MIN_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound of the delay between retries.
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The keys of a [`Reflector`] at a revision.
+#[derive(Debug, Clone)]
+pub struct ReflectorSnapshot {
+    pub revision: i64,
+    pub kvs: BTreeMap<Vec<u8>, KeyValue>,
+}
+
+#[derive(Default)]
+struct Cache {
+    kvs: BTreeMap<Vec<u8>, KeyValue>,
+    revision: i64,
+    subscribers: Vec<UnboundedSender<Event>>,
+}
+
+impl Cache {
+    fn publish(&mut self, event: Event) {
+        self.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    /// Applies an event of the watch, with the cached key value as its previous key value.
+    fn apply(&mut self, mut event: Event) {
+        event.prev_kv = match event.event_type {
+            EventType::Put => self.kvs.insert(event.kv.key.clone(), event.kv.clone()),
+            EventType::Delete => self.kvs.remove(&event.kv.key),
+        };
+        self.publish(event);
+    }
+
+    /// Replaces the key values with a new list, and publishes the difference as events.
+    fn replace(&mut self, kvs: Vec<KeyValue>, revision: i64) {
+        let mut old = std::mem::take(&mut self.kvs);
+        for kv in kvs {
+            let prev_kv = old.remove(&kv.key);
+            self.kvs.insert(kv.key.clone(), kv.clone());
+            if prev_kv.as_ref().map(|prev| prev.mod_revision) != Some(kv.mod_revision) {
+                self.publish(Event {
+                    event_type: EventType::Put,
+                    kv,
+                    prev_kv,
+                });
+            }
+        }
+
+        for (key, prev_kv) in old {
+            self.publish(Event {
+                event_type: EventType::Delete,
+                kv: KeyValue {
+                    key,
+                    mod_revision: revision,
+                    ..Default::default()
+                },
+                prev_kv: Some(prev_kv),
+            });
+        }
+        self.revision = revision;
+    }
+}
+
+/// Reflector keeps a local copy of the keys under a prefix.
+///
+/// The prefix is listed, then watched from the revision of the list. The keys are listed again when the
+/// watch cannot resume because its revision has been compacted, and the watch is retried when it is
+/// interrupted, such as by a member going down.
+///
+/// Clones share the cache, which is kept in sync until every clone is dropped.
+#[derive(Clone)]
+pub struct Reflector {
+    cache: Arc<RwLock<Cache>>,
+    revision: watch::Receiver<i64>,
+}
+
+impl Reflector {
+    /// Lists the keys under the prefix, then keeps them in sync in the background.
+    pub async fn start<C>(client: C, prefix: impl Into<Vec<u8>>) -> Result<Self>
+    where
+        C: KeyValueOp + WatchOp + Send + Sync + 'static,
+    {
+        let prefix = prefix.into();
+        let cache = Arc::new(RwLock::new(Cache::default()));
+        let (tx, revision) = watch::channel(0);
+
+        let task = Syncer {
+            client,
+            prefix,
+            cache: Arc::clone(&cache),
+            revision: tx,
+        };
+        task.list().await?;
+
+        tokio::spawn(async move {
+            future::select(Box::pin(task.revision.closed()), Box::pin(task.run(None))).await;
+        });
+
+        Ok(Self { cache, revision })
+    }
+
+    fn cache(&self) -> RwLockReadGuard<'_, Cache> {
+        self.cache.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Gets the cached key value of the key.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<KeyValue> {
+        self.cache().kvs.get(key.as_ref()).cloned()
+    }
+
+    /// Gets the cached key values in the range, ordered by key.
+    pub fn range(&self, range: impl Into<KeyRange>) -> Vec<KeyValue> {
+        let KeyRange { key, range_end } = range.into();
+        if range_end.is_empty() {
+            return self.get(key).into_iter().collect();
+        }
+
+        // a range end of "\0" means every key from the key
+        let end = if range_end == [0] {
+            Bound::Unbounded
+        } else if range_end <= key {
+            return vec![];
+        } else {
+            Bound::Excluded(range_end)
+        };
+        self.cache()
+            .kvs
+            .range((Bound::Included(key), end))
+            .map(|(_, kv)| kv.clone())
+            .collect()
+    }
+
+    /// Gets the revision the cache is in sync with.
+    pub fn revision(&self) -> i64 {
+        *self.revision.borrow()
+    }
+
+    /// Gets a snapshot of the cache, once it is in sync with the revision at least.
+    ///
+    /// The snapshot is the latest state of the cache, so its revision may be after the requested one.
+    /// Writes outside the prefix do not advance the cache, so the revision should be one of a write under
+    /// the prefix, such as to read it back.
+    pub async fn snapshot_after(&self, revision: i64) -> Result<ReflectorSnapshot> {
+        let mut rx = self.revision.clone();
+        while *rx.borrow_and_update() < revision {
+            rx.changed().await.map_err(|_| Error::ChannelClosed)?;
+        }
+
+        let cache = self.cache();
+        Ok(ReflectorSnapshot {
+            revision: cache.revision,
+            kvs: cache.kvs.clone(),
+        })
+    }
+
+    /// Subscribes to the changes of the cache after now.
+    ///
+    /// When the keys are listed again, the difference from the cache is sent as events, and keys which
+    /// are gone are deleted at the revision of the list.
+    pub fn changes(&self) -> BoxStream<'static, Event> {
+        self.subscribe().1
+    }
+
+    /// Takes a snapshot of the cache, and subscribes to the changes after it.
+    pub(crate) fn subscribe(&self) -> (ReflectorSnapshot, BoxStream<'static, Event>) {
+        let (tx, rx) = unbounded_channel();
+        let mut cache = self.cache.write().unwrap_or_else(PoisonError::into_inner);
+        cache.subscribers.push(tx);
+        let snapshot = ReflectorSnapshot {
+            revision: cache.revision,
+            kvs: cache.kvs.clone(),
+        };
+        (snapshot, UnboundedReceiverStream::new(rx).boxed())
+    }
+}
+
+/// The background task of a [`Reflector`].
+struct Syncer<C> {
+    client: C,
+    prefix: Vec<u8>,
+    cache: Arc<RwLock<Cache>>,
+    revision: watch::Sender<i64>,
+}
+
+impl<C> Syncer<C>
+where
+    C: KeyValueOp + WatchOp + Send + Sync + 'static,
+{
+    fn cache(&self) -> RwLockWriteGuard<'_, Cache> {
+        self.cache.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn list(&self) -> Result<()> {
+        let resp = self.client.get_by_prefix(self.prefix.clone()).await?;
+        let revision = resp.header.revision();
+        self.cache().replace(resp.kvs, revision);
+        self.revision.send_replace(revision);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C> WatchLoop for Syncer<C>
+where
+    C: KeyValueOp + WatchOp + Send + Sync + 'static,
+{
+    type Client = C;
+
+    fn client(&self) -> &C {
+        &self.client
+    }
+
+    fn range(&self) -> KeyRange {
+        KeyRange::prefix(self.prefix.clone())
+    }
+
+    fn revision(&self) -> i64 {
+        self.cache().revision
+    }
+
+    fn apply(&self, events: Vec<Event>, revision: i64) {
+        let mut cache = self.cache();
+        for event in events {
+            cache.apply(event);
+        }
+        cache.revision = cache.revision.max(revision);
+        self.revision.send_replace(cache.revision);
+    }
+
+    async fn relist(&self) -> Result<()> {
+        self.list().await
+    }
+}
+
+/// State kept in sync with a key range by a watch, which is listed again when the watch cannot resume.
+///
+/// The loop is shared by the [`Reflector`] and the [`CachedKv`](crate::CachedKv).
+#[async_trait]
+pub(crate) trait WatchLoop: Send + Sync {
+    type Client: WatchOp + Send + Sync;
+
+    fn client(&self) -> &Self::Client;
+
+    /// Gets the key range to watch.
+    fn range(&self) -> KeyRange;
+
+    /// Gets the revision the state is in sync with, the watch resumes after it.
+    fn revision(&self) -> i64;
+
+    /// Marks whether a watch is running, the state may miss events while it is not.
+    fn set_watching(&self, _watching: bool) {}
+
+    /// Applies the events of a watch response, and advances the state to the revision of the response.
+    fn apply(&self, events: Vec<Event>, revision: i64);
+
+    /// Reads the state again, since the events after its revision have been compacted.
+    async fn relist(&self) -> Result<()>;
+
+    /// Watches the range from the revision of the state.
+    async fn create(&self) -> Result<(WatchStream, WatchCanceler)> {
+        let req = WatchCreateRequest::create(self.range())
+            .start_revision(self.revision() + 1)
+            .progress_notify();
+        let created = self.client().watch(req).await?;
+        self.set_watching(true);
+        Ok(created)
+    }
+
+    /// Watches from the revision of the state, and lists again when it has been compacted.
+    ///
+    /// The first watch is the one passed if any.
+    async fn run(&self, mut created: Option<(WatchStream, WatchCanceler)>) {
+        let mut retry_interval = MIN_RETRY_INTERVAL;
+
+        loop {
+            let end = self.watch(created.take()).await;
+            self.set_watching(false);
+
+            match end {
+                WatchEnd::Compacted => {
+                    while self.relist().await.is_err() {
+                        backoff(&mut retry_interval).await;
+                    }
+                    retry_interval = MIN_RETRY_INTERVAL;
+                }
+                WatchEnd::Interrupted => retry_interval = MIN_RETRY_INTERVAL,
+                WatchEnd::Failed => backoff(&mut retry_interval).await,
+            }
+        }
+    }
+
+    /// Applies the events of a watch until it ends, a new watch is created if none is passed.
+    async fn watch(&self, created: Option<(WatchStream, WatchCanceler)>) -> WatchEnd {
+        let created = match created {
+            Some(created) => Ok(created),
+            None => self.create().await,
+        };
+        let Ok((mut stream, _canceler)) = created else {
+            return WatchEnd::Failed;
+        };
+
+        let mut end = WatchEnd::Failed;
+        while let Some(WatchInbound::Ready(resp)) = stream.next().await {
+            if resp.compact_revision > 0 {
+                return WatchEnd::Compacted;
+            }
+            if resp.canceled {
+                break;
+            }
+
+            end = WatchEnd::Interrupted;
+            let revision = resp.header.revision();
+            self.apply(resp.events, revision);
+        }
+        end
+    }
+}
+
+/// Sleeps for the retry interval, and doubles it for the next retry.
+async fn backoff(retry_interval: &mut Duration) {
+    let delay = *retry_interval;
+    *retry_interval = (delay * 2).min(MAX_RETRY_INTERVAL);
+    tokio::time::sleep(delay).await;
+}
+
+/// How a watch of the range ended.
+pub(crate) enum WatchEnd {
+    /// The revision to watch from has been compacted, so the keys have to be read again.
+    Compacted,
+    /// The watch received responses before it was interrupted, so it is retried at once.
+    Interrupted,
+    /// The watch failed without any response, so it is retried after a delay.
+    Failed,
+}
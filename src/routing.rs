@@ -0,0 +1,223 @@
+//! Leader-aware routing, which sends mutations to the leader and spreads reads across the followers.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use futures::future;
+use tokio::sync::Notify;
+use tonic::transport::Channel;
+
+use crate::balance::EndpointSet;
+use crate::client::TokenInterceptor;
+use crate::proto::etcdserverpb;
+use crate::proto::etcdserverpb::cluster_client::ClusterClient;
+use crate::proto::etcdserverpb::maintenance_client::MaintenanceClient;
+use crate::Result;
+
+/// How often the leader is discovered again, besides when the raft term changes.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Timeout of the requests discovering the leader.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How requests are routed to the members of the cluster.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoutingPolicy {
+    /// Every request is balanced across the endpoints, members forward mutations to the leader.
+    #[default]
+    Balanced,
+    /// Mutations, which are put, delete, txn, lease grant and lease revoke, are sent to the leader directly,
+    /// and the other requests are spread across the followers.
+    ///
+    /// The leader is discovered by `Maintenance::Status` and the client URLs of the members, and discovered
+    /// again when the raft term of a response changes. Mutations are balanced across all endpoints while
+    /// the leader is unknown or is not one of the endpoints.
+    Leader,
+}
+
+/// Keeps the endpoints of mutations on the leader, and the endpoints of reads on the followers.
+pub(crate) struct LeaderRouter {
+    reads: Arc<EndpointSet>,
+    writes: Arc<EndpointSet>,
+    token: Option<String>,
+    leader: Mutex<Option<String>>,
+    term: AtomicU64,
+    stale: Notify,
+}
+
+impl LeaderRouter {
+    /// Discovers the leader, then keeps discovering it until the balanced channels are dropped.
+    pub(crate) async fn start(
+        reads: Arc<EndpointSet>,
+        writes: Arc<EndpointSet>,
+        token: Option<String>,
+    ) -> Result<Arc<Self>> {
+        let router = Arc::new(Self {
+            reads,
+            writes,
+            token,
+            leader: Mutex::new(None),
+            term: AtomicU64::new(0),
+            stale: Notify::new(),
+        });
+
+        let mut channels = HashMap::new();
+        let mut versions = HashMap::new();
+        router.route(&mut channels, &mut versions).await?;
+
+        let task = Arc::clone(&router);
+        tokio::spawn(async move {
+            loop {
+                let wait = future::select(
+                    Box::pin(tokio::time::sleep(REFRESH_INTERVAL)),
+                    Box::pin(task.stale.notified()),
+                );
+                if let future::Either::Left(_) =
+                    future::select(Box::pin(task.writes.closed()), wait).await
+                {
+                    return;
+                }
+
+                if task.route(&mut channels, &mut versions).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(router)
+    }
+
+    /// Returns the URL of the endpoint of the leader, if known.
+    pub(crate) fn leader(&self) -> Option<String> {
+        self.leader
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Observes the raft term of a response, the leader is discovered again in a new term.
+    pub(crate) fn observe(&self, raft_term: u64) {
+        let term = self.term.fetch_max(raft_term, Ordering::SeqCst);
+        if term != 0 && raft_term > term {
+            self.stale.notify_one();
+        }
+    }
+
+    /// Discovers the leader again, such as when it cannot be reached.
+    pub(crate) fn invalidate(&self) {
+        self.stale.notify_one();
+    }
+
+    /// Discovers the leader, and updates the endpoints of mutations and reads.
+    async fn route(
+        &self,
+        channels: &mut HashMap<String, (u64, Channel)>,
+        versions: &mut HashMap<String, u64>,
+    ) -> Result<()> {
+        let endpoints = self.reads.endpoints();
+        channels.retain(|url, _| endpoints.iter().any(|(u, _, _)| u == url));
+        for (url, version, endpoint) in endpoints.iter() {
+            match channels.get(url) {
+                Some((v, _)) if v == version => {}
+                _ => {
+                    channels.insert(url.clone(), (*version, endpoint.connect_lazy()));
+                }
+            }
+        }
+
+        let leader = self.discover(channels).await;
+        let writes = endpoints
+            .into_iter()
+            .filter(|(url, _, _)| leader.is_none() || leader.as_ref() == Some(url))
+            .collect::<Vec<_>>();
+
+        self.writes
+            .set(
+                writes
+                    .iter()
+                    .map(|(url, _, endpoint)| (url.clone(), endpoint.clone()))
+                    .collect(),
+            )
+            .await?;
+        // connections are rebuilt when the endpoints are replaced, such as by certificate reload
+        versions.retain(|url, _| writes.iter().any(|(u, _, _)| u == url));
+        for (url, version, endpoint) in writes {
+            match versions.insert(url.clone(), version) {
+                Some(v) if v != version => self.writes.replace(&url, endpoint).await?,
+                _ => {}
+            }
+        }
+
+        self.reads.exclude(leader.clone()).await?;
+        *self.leader.lock().unwrap_or_else(PoisonError::into_inner) = leader;
+        Ok(())
+    }
+
+    /// Finds the endpoint of the leader with the status of the members.
+    async fn discover(&self, channels: &HashMap<String, (u64, Channel)>) -> Option<String> {
+        let interceptor = TokenInterceptor::new(self.token.clone());
+        let statuses = future::join_all(channels.iter().map(|(url, (_, channel))| {
+            let mut client =
+                MaintenanceClient::with_interceptor(channel.clone(), interceptor.clone());
+            async move {
+                let status = tokio::time::timeout(
+                    DISCOVERY_TIMEOUT,
+                    client.status(etcdserverpb::StatusRequest {}),
+                )
+                .await
+                .ok()?
+                .ok()?
+                .into_inner();
+                Some((url, status))
+            }
+        }))
+        .await;
+
+        let (_, latest) = statuses
+            .iter()
+            .flatten()
+            .filter(|(_, status)| status.leader != 0)
+            .max_by_key(|(_, status)| status.raft_term)?;
+        self.term.fetch_max(latest.raft_term, Ordering::SeqCst);
+        let leader = latest.leader;
+
+        // the leader answered by itself
+        if let Some((url, _)) = statuses.iter().flatten().find(|(_, status)| {
+            status.header.as_ref().map(|header| header.member_id) == Some(leader)
+        }) {
+            return Some((*url).clone());
+        }
+
+        // otherwise, the client URLs of the leader are matched against the endpoints
+        for (url, _) in statuses.iter().flatten() {
+            let (_, channel) = &channels[*url];
+            let mut client = ClusterClient::with_interceptor(channel.clone(), interceptor.clone());
+            let Ok(Ok(members)) = tokio::time::timeout(
+                DISCOVERY_TIMEOUT,
+                client.member_list(etcdserverpb::MemberListRequest::default()),
+            )
+            .await
+            else {
+                continue;
+            };
+
+            let member = members
+                .into_inner()
+                .members
+                .into_iter()
+                .find(|member| member.id == leader)?;
+            return channels
+                .keys()
+                .find(|url| {
+                    member.client_ur_ls.iter().any(|client_url| {
+                        client_url.trim_end_matches('/') == url.trim_end_matches('/')
+                    })
+                })
+                .cloned();
+        }
+
+        None
+    }
+}
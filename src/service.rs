@@ -0,0 +1,364 @@
+//! Object-safe companions of the operation traits.
+//!
+//! The operation traits such as [`KeyValueOp`] have generic methods, so they cannot be used as trait objects.
+//! The service traits take concrete request types instead, which allows storing clients as `Arc<dyn KvService>`
+//! and stacking decorators on top of them.
+//!
+//! Every type implementing an operation trait implements the service trait, and `Arc<dyn KvService>`
+//! implements [`KeyValueOp`] again, so it can be passed to anything expecting a client, such as [`Namespaced`].
+//!
+//! The method names are shared with the operation traits, so only import one of them in the same scope.
+//!
+//! [`Namespaced`]: crate::Namespaced
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::auth::{AuthOp, AuthenticateRequest, AuthenticateResponse};
+use crate::cluster::{
+    ClusterOp, MemberAddRequest, MemberAddResponse, MemberListResponse, MemberRemoveRequest,
+    MemberRemoveResponse, MemberUpdateRequest, MemberUpdateResponse,
+};
+use crate::kv::{
+    CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValueOp,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, RequestLimits, TxnRequest, TxnResponse,
+};
+use crate::lease::{
+    LeaseGrantRequest, LeaseGrantResponse, LeaseId, LeaseKeepAlive, LeaseOp, LeaseRevokeRequest,
+    LeaseRevokeResponse, LeaseTimeToLiveRequest, LeaseTimeToLiveResponse,
+};
+use crate::watch::{WatchCanceler, WatchCreateRequest, WatchOp, WatchStream};
+use crate::Result;
+
+/// Object-safe [`KeyValueOp`].
+#[async_trait]
+pub trait KvService: Send + Sync {
+    async fn put(&self, req: PutRequest) -> Result<PutResponse>;
+
+    async fn get(&self, req: RangeRequest) -> Result<RangeResponse>;
+
+    async fn delete(&self, req: DeleteRequest) -> Result<DeleteResponse>;
+
+    async fn txn(&self, req: TxnRequest) -> Result<TxnResponse>;
+
+    async fn compact(&self, req: CompactRequest) -> Result<CompactResponse>;
+
+    /// See [`KeyValueOp::get_linearizable`].
+    async fn get_linearizable(&self, req: RangeRequest) -> Result<RangeResponse>;
+
+    /// See [`KeyValueOp::request_limits`].
+    fn request_limits(&self) -> RequestLimits;
+}
+
+/// Object-safe [`WatchOp`].
+#[async_trait]
+pub trait WatchService: Send + Sync {
+    async fn watch(&self, req: WatchCreateRequest) -> Result<(WatchStream, WatchCanceler)>;
+}
+
+/// Object-safe [`LeaseOp`].
+#[async_trait]
+pub trait LeaseService: Send + Sync {
+    async fn grant_lease(&self, req: LeaseGrantRequest) -> Result<LeaseGrantResponse>;
+
+    async fn revoke(&self, req: LeaseRevokeRequest) -> Result<LeaseRevokeResponse>;
+
+    async fn keep_alive_for(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive>;
+
+    async fn time_to_live(&self, req: LeaseTimeToLiveRequest) -> Result<LeaseTimeToLiveResponse>;
+}
+
+/// Object-safe [`ClusterOp`].
+#[async_trait]
+pub trait ClusterService: Send + Sync {
+    async fn member_add(&self, req: MemberAddRequest) -> Result<MemberAddResponse>;
+
+    async fn member_remove(&self, req: MemberRemoveRequest) -> Result<MemberRemoveResponse>;
+
+    async fn member_update(&self, req: MemberUpdateRequest) -> Result<MemberUpdateResponse>;
+
+    async fn member_list(&self) -> Result<MemberListResponse>;
+}
+
+/// Object-safe [`AuthOp`].
+#[async_trait]
+pub trait AuthService: Send + Sync {
+    async fn authenticate(&self, req: AuthenticateRequest) -> Result<AuthenticateResponse>;
+}
+
+#[async_trait]
+impl<T> KvService for T
+where
+    T: KeyValueOp + Send + Sync,
+{
+    async fn put(&self, req: PutRequest) -> Result<PutResponse> {
+        KeyValueOp::put(self, req).await
+    }
+
+    async fn get(&self, req: RangeRequest) -> Result<RangeResponse> {
+        KeyValueOp::get(self, req).await
+    }
+
+    async fn delete(&self, req: DeleteRequest) -> Result<DeleteResponse> {
+        KeyValueOp::delete(self, req).await
+    }
+
+    async fn txn(&self, req: TxnRequest) -> Result<TxnResponse> {
+        KeyValueOp::txn(self, req).await
+    }
+
+    async fn compact(&self, req: CompactRequest) -> Result<CompactResponse> {
+        KeyValueOp::compact(self, req).await
+    }
+
+    async fn get_linearizable(&self, req: RangeRequest) -> Result<RangeResponse> {
+        KeyValueOp::get_linearizable(self, req).await
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        KeyValueOp::request_limits(self)
+    }
+}
+
+#[async_trait]
+impl<T> WatchService for T
+where
+    T: WatchOp + Send + Sync,
+{
+    async fn watch(&self, req: WatchCreateRequest) -> Result<(WatchStream, WatchCanceler)> {
+        WatchOp::watch(self, req).await
+    }
+}
+
+#[async_trait]
+impl<T> LeaseService for T
+where
+    T: LeaseOp + Send + Sync,
+{
+    async fn grant_lease(&self, req: LeaseGrantRequest) -> Result<LeaseGrantResponse> {
+        LeaseOp::grant_lease(self, req).await
+    }
+
+    async fn revoke(&self, req: LeaseRevokeRequest) -> Result<LeaseRevokeResponse> {
+        LeaseOp::revoke(self, req).await
+    }
+
+    async fn keep_alive_for(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive> {
+        LeaseOp::keep_alive_for(self, lease_id).await
+    }
+
+    async fn time_to_live(&self, req: LeaseTimeToLiveRequest) -> Result<LeaseTimeToLiveResponse> {
+        LeaseOp::time_to_live(self, req).await
+    }
+}
+
+#[async_trait]
+impl<T> ClusterService for T
+where
+    T: ClusterOp + Send + Sync,
+{
+    async fn member_add(&self, req: MemberAddRequest) -> Result<MemberAddResponse> {
+        ClusterOp::member_add(self, req).await
+    }
+
+    async fn member_remove(&self, req: MemberRemoveRequest) -> Result<MemberRemoveResponse> {
+        ClusterOp::member_remove(self, req).await
+    }
+
+    async fn member_update(&self, req: MemberUpdateRequest) -> Result<MemberUpdateResponse> {
+        ClusterOp::member_update(self, req).await
+    }
+
+    async fn member_list(&self) -> Result<MemberListResponse> {
+        ClusterOp::member_list(self).await
+    }
+}
+
+#[async_trait]
+impl<T> AuthService for T
+where
+    T: AuthOp + Send + Sync,
+{
+    async fn authenticate(&self, req: AuthenticateRequest) -> Result<AuthenticateResponse> {
+        AuthOp::authenticate(self, req).await
+    }
+}
+
+#[async_trait]
+impl<S> KeyValueOp for Arc<S>
+where
+    S: KvService + ?Sized,
+{
+    async fn put<R>(&self, req: R) -> Result<PutResponse>
+    where
+        R: Into<PutRequest> + Send,
+    {
+        KvService::put(&**self, req.into()).await
+    }
+
+    async fn get<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        KvService::get(&**self, req.into()).await
+    }
+
+    async fn get_all(&self) -> Result<RangeResponse> {
+        KeyValueOp::get(self, KeyRange::all()).await
+    }
+
+    async fn get_by_prefix<K>(&self, p: K) -> Result<RangeResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        KeyValueOp::get(self, KeyRange::prefix(p)).await
+    }
+
+    async fn get_range<F, E>(&self, from: F, end: E) -> Result<RangeResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        KeyValueOp::get(self, KeyRange::range(from, end)).await
+    }
+
+    async fn delete<R>(&self, req: R) -> Result<DeleteResponse>
+    where
+        R: Into<DeleteRequest> + Send,
+    {
+        KvService::delete(&**self, req.into()).await
+    }
+
+    async fn delete_all(&self) -> Result<DeleteResponse> {
+        KeyValueOp::delete(self, KeyRange::all()).await
+    }
+
+    async fn delete_by_prefix<K>(&self, p: K) -> Result<DeleteResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        KeyValueOp::delete(self, KeyRange::prefix(p)).await
+    }
+
+    async fn delete_range<F, E>(&self, from: F, end: E) -> Result<DeleteResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        KeyValueOp::delete(self, KeyRange::range(from, end)).await
+    }
+
+    async fn txn<R>(&self, req: R) -> Result<TxnResponse>
+    where
+        R: Into<TxnRequest> + Send,
+    {
+        KvService::txn(&**self, req.into()).await
+    }
+
+    async fn compact<R>(&self, req: R) -> Result<CompactResponse>
+    where
+        R: Into<CompactRequest> + Send,
+    {
+        KvService::compact(&**self, req.into()).await
+    }
+
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        KvService::get_linearizable(&**self, req.into()).await
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        KvService::request_limits(&**self)
+    }
+}
+
+#[async_trait]
+impl<S> WatchOp for Arc<S>
+where
+    S: WatchService + ?Sized,
+{
+    async fn watch<R>(&self, req: R) -> Result<(WatchStream, WatchCanceler)>
+    where
+        R: Into<WatchCreateRequest> + Send,
+    {
+        WatchService::watch(&**self, req.into()).await
+    }
+}
+
+#[async_trait]
+impl<S> LeaseOp for Arc<S>
+where
+    S: LeaseService + ?Sized,
+{
+    async fn grant_lease<R>(&self, req: R) -> Result<LeaseGrantResponse>
+    where
+        R: Into<LeaseGrantRequest> + Send,
+    {
+        LeaseService::grant_lease(&**self, req.into()).await
+    }
+
+    async fn revoke<R>(&self, req: R) -> Result<LeaseRevokeResponse>
+    where
+        R: Into<LeaseRevokeRequest> + Send,
+    {
+        LeaseService::revoke(&**self, req.into()).await
+    }
+
+    async fn keep_alive_for(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive> {
+        LeaseService::keep_alive_for(&**self, lease_id).await
+    }
+
+    async fn time_to_live<R>(&self, req: R) -> Result<LeaseTimeToLiveResponse>
+    where
+        R: Into<LeaseTimeToLiveRequest> + Send,
+    {
+        LeaseService::time_to_live(&**self, req.into()).await
+    }
+}
+
+#[async_trait]
+impl<S> ClusterOp for Arc<S>
+where
+    S: ClusterService + ?Sized,
+{
+    async fn member_add<R>(&self, req: R) -> Result<MemberAddResponse>
+    where
+        R: Into<MemberAddRequest> + Send,
+    {
+        ClusterService::member_add(&**self, req.into()).await
+    }
+
+    async fn member_remove<R>(&self, req: R) -> Result<MemberRemoveResponse>
+    where
+        R: Into<MemberRemoveRequest> + Send,
+    {
+        ClusterService::member_remove(&**self, req.into()).await
+    }
+
+    async fn member_update<R>(&self, req: R) -> Result<MemberUpdateResponse>
+    where
+        R: Into<MemberUpdateRequest> + Send,
+    {
+        ClusterService::member_update(&**self, req.into()).await
+    }
+
+    async fn member_list(&self) -> Result<MemberListResponse> {
+        ClusterService::member_list(&**self).await
+    }
+}
+
+#[async_trait]
+impl<S> AuthOp for Arc<S>
+where
+    S: AuthService + ?Sized,
+{
+    async fn authenticate<R>(&self, req: R) -> Result<AuthenticateResponse>
+    where
+        R: Into<AuthenticateRequest> + Send,
+    {
+        AuthService::authenticate(&**self, req.into()).await
+    }
+}
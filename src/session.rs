@@ -0,0 +1,187 @@
+//! Read-your-writes consistency over serializable reads.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::client::{CallOptions, Client};
+use crate::kv::{
+    CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValueOp,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, ReadConsistency, RequestLimits,
+    TxnRequest, TxnResponse,
+};
+use crate::proto::etcdserverpb;
+use crate::Result;
+
+/// ConsistentSession reads its own writes, while its reads are served by any member.
+///
+/// The revision of every write through the session is recorded as its consistency token. Reads are serializable,
+/// and a read served by a member whose revision is behind the token is retried, which may reach another member.
+/// Once the retries are exhausted, the read is linearizable instead.
+///
+/// Clones share the token, and [`ConsistentSession::advance`] passes a token from elsewhere, such as another service.
+#[derive(Clone)]
+pub struct ConsistentSession {
+    client: Client,
+    token: Arc<AtomicI64>,
+    retries: usize,
+    retry_interval: Duration,
+}
+
+impl ConsistentSession {
+    /// Creates a session without a token, which retries stale reads 3 times every 20 milliseconds.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            token: Arc::new(AtomicI64::new(0)),
+            retries: 3,
+            retry_interval: Duration::from_millis(20),
+        }
+    }
+
+    /// Sets how many times a stale read is retried before reading linearizably.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets how long to wait before retrying a stale read.
+    pub fn retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Gets the consistency token, which is the latest revision written through the session.
+    pub fn token(&self) -> i64 {
+        self.token.load(Ordering::SeqCst)
+    }
+
+    /// Advances the token to the revision, so later reads see it at least.
+    pub fn advance(&self, revision: i64) {
+        self.token.fetch_max(revision, Ordering::SeqCst);
+    }
+
+    /// Gets the underlying client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl KeyValueOp for ConsistentSession {
+    async fn put<R>(&self, req: R) -> Result<PutResponse>
+    where
+        R: Into<PutRequest> + Send,
+    {
+        let resp = self.client.put(req).await?;
+        self.advance(resp.header.revision());
+        Ok(resp)
+    }
+
+    async fn get<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        let req: etcdserverpb::RangeRequest = req.into().into();
+        let token = self.token();
+
+        let serializable = self
+            .client
+            .with_call_options(CallOptions::new().read_consistency(ReadConsistency::Serializable));
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                tokio::time::sleep(self.retry_interval).await;
+            }
+
+            let resp = serializable.get(RangeRequest::from(req.clone())).await?;
+            if resp.header.revision() >= token {
+                return Ok(resp);
+            }
+        }
+
+        self.client.get_linearizable(RangeRequest::from(req)).await
+    }
+
+    async fn get_all(&self) -> Result<RangeResponse> {
+        self.get(KeyRange::all()).await
+    }
+
+    async fn get_by_prefix<K>(&self, p: K) -> Result<RangeResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.get(KeyRange::prefix(p)).await
+    }
+
+    async fn get_range<F, E>(&self, from: F, end: E) -> Result<RangeResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.get(KeyRange::range(from, end)).await
+    }
+
+    async fn delete<R>(&self, req: R) -> Result<DeleteResponse>
+    where
+        R: Into<DeleteRequest> + Send,
+    {
+        let resp = self.client.delete(req).await?;
+        self.advance(resp.header.revision());
+        Ok(resp)
+    }
+
+    async fn delete_all(&self) -> Result<DeleteResponse> {
+        self.delete(KeyRange::all()).await
+    }
+
+    async fn delete_by_prefix<K>(&self, p: K) -> Result<DeleteResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::prefix(p)).await
+    }
+
+    async fn delete_range<F, E>(&self, from: F, end: E) -> Result<DeleteResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::range(from, end)).await
+    }
+
+    async fn txn<R>(&self, req: R) -> Result<TxnResponse>
+    where
+        R: Into<TxnRequest> + Send,
+    {
+        let resp = self.client.txn(req).await?;
+        self.advance(resp.header.revision());
+        Ok(resp)
+    }
+
+    async fn compact<R>(&self, req: R) -> Result<CompactResponse>
+    where
+        R: Into<CompactRequest> + Send,
+    {
+        self.client.compact(req).await
+    }
+
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        self.client.get_linearizable(req).await
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        self.client.request_limits()
+    }
+}
+
+impl Client {
+    /// Creates a [`ConsistentSession`], which reads its own writes with serializable reads.
+    pub fn consistent_session(&self) -> ConsistentSession {
+        ConsistentSession::new(self.clone())
+    }
+}
@@ -0,0 +1,263 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::ready;
+use futures::StreamExt;
+use tokio::sync::mpsc::{channel, unbounded_channel};
+use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
+
+use super::store::Store;
+use crate::kv::{
+    CompactRequest, CompactResponse, DeleteRequest, DeleteResponse, KeyRange, KeyValueOp,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, RequestLimits, TxnRequest, TxnResponse,
+};
+use crate::lease::{
+    LeaseGrantRequest, LeaseGrantResponse, LeaseId, LeaseKeepAlive, LeaseOp, LeaseRevokeRequest,
+    LeaseRevokeResponse, LeaseTimeToLiveRequest, LeaseTimeToLiveResponse,
+};
+use crate::proto::etcdserverpb;
+use crate::proto::etcdserverpb::watch_request::RequestUnion;
+use crate::watch::{WatchCanceler, WatchCreateRequest, WatchOp, WatchStream};
+use crate::Result;
+
+/// FakeClient is an in-memory etcd for unit tests.
+///
+/// It implements the same operations as [`Client`](crate::Client) with etcd MVCC semantics,
+/// including revisions, transactions, watches, leases and compaction.
+/// Time only moves forward with [`FakeClient::advance`], so lease expiry is deterministic.
+///
+/// Clones of a FakeClient share the same store.
+#[derive(Clone)]
+pub struct FakeClient {
+    store: Arc<Mutex<Store>>,
+    limits: RequestLimits,
+}
+
+impl FakeClient {
+    /// Creates a new FakeClient with an empty store.
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(Mutex::new(Store::new())),
+            limits: RequestLimits::default(),
+        }
+    }
+
+    /// Sets the request limits which are checked before applying requests.
+    pub fn with_request_limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Moves the clock forward, leases whose TTL elapsed are expired along with their keys.
+    pub fn advance(&self, duration: Duration) {
+        self.store().advance(duration);
+    }
+
+    /// Gets the current revision of the store.
+    pub fn revision(&self) -> i64 {
+        self.store().revision()
+    }
+
+    /// Gets the revision of the last compaction.
+    pub fn compact_revision(&self) -> i64 {
+        self.store().compact_revision()
+    }
+
+    #[cfg(feature = "mock-server")]
+    pub(crate) fn shared_store(&self) -> Arc<Mutex<Store>> {
+        Arc::clone(&self.store)
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().expect("lock fake etcd store")
+    }
+}
+
+impl Default for FakeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl KeyValueOp for FakeClient {
+    async fn put<R>(&self, req: R) -> Result<PutResponse>
+    where
+        R: Into<PutRequest> + Send,
+    {
+        let req = req.into();
+        req.check_limits(&self.limits)?;
+
+        let resp = self.store().put(req.into())?;
+
+        Ok(resp.into())
+    }
+
+    async fn get<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        let resp = self.store().range(req.into().into())?;
+
+        Ok(resp.into())
+    }
+
+    async fn get_all(&self) -> Result<RangeResponse> {
+        self.get(KeyRange::all()).await
+    }
+
+    async fn get_by_prefix<K>(&self, p: K) -> Result<RangeResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.get(KeyRange::prefix(p)).await
+    }
+
+    async fn get_range<F, E>(&self, from: F, end: E) -> Result<RangeResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.get(KeyRange::range(from, end)).await
+    }
+
+    async fn delete<R>(&self, req: R) -> Result<DeleteResponse>
+    where
+        R: Into<DeleteRequest> + Send,
+    {
+        let req = req.into();
+        req.check_limits(&self.limits)?;
+
+        let resp = self.store().delete_range(req.into())?;
+
+        Ok(resp.into())
+    }
+
+    async fn delete_all(&self) -> Result<DeleteResponse> {
+        self.delete(KeyRange::all()).await
+    }
+
+    async fn delete_by_prefix<K>(&self, p: K) -> Result<DeleteResponse>
+    where
+        K: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::prefix(p)).await
+    }
+
+    async fn delete_range<F, E>(&self, from: F, end: E) -> Result<DeleteResponse>
+    where
+        F: Into<Vec<u8>> + Send,
+        E: Into<Vec<u8>> + Send,
+    {
+        self.delete(KeyRange::range(from, end)).await
+    }
+
+    async fn txn<R>(&self, req: R) -> Result<TxnResponse>
+    where
+        R: Into<TxnRequest> + Send,
+    {
+        let req = req.into();
+        req.check_limits(&self.limits)?;
+
+        let resp = self.store().txn(req.into())?;
+
+        Ok(resp.into())
+    }
+
+    async fn compact<R>(&self, req: R) -> Result<CompactResponse>
+    where
+        R: Into<CompactRequest> + Send,
+    {
+        let resp = self.store().compact(req.into().into())?;
+
+        Ok(resp.into())
+    }
+
+    /// Reads are always linearizable, since the store is the only member.
+    async fn get_linearizable<R>(&self, req: R) -> Result<RangeResponse>
+    where
+        R: Into<RangeRequest> + Send,
+    {
+        self.get(req).await
+    }
+
+    fn request_limits(&self) -> RequestLimits {
+        self.limits
+    }
+}
+
+#[async_trait]
+impl WatchOp for FakeClient {
+    async fn watch<R>(&self, req: R) -> Result<(WatchStream, WatchCanceler)>
+    where
+        R: Into<WatchCreateRequest> + Send,
+    {
+        let (tx, rx) = unbounded_channel();
+        let watch_id = self.store().watch(req.into().into(), tx);
+
+        // cancel requests are applied to the store once the stream is polled,
+        // the canceled response follows the events already sent.
+        let (cancel_tx, cancel_rx) = channel::<etcdserverpb::WatchRequest>(1);
+        let store = Arc::clone(&self.store);
+        let cancels = ReceiverStream::new(cancel_rx).filter_map(move |req| {
+            if let Some(RequestUnion::CancelRequest(req)) = req.request_union {
+                store
+                    .lock()
+                    .expect("lock fake etcd store")
+                    .cancel_watch(req.watch_id);
+            }
+            ready(None)
+        });
+
+        let stream = futures::stream::select(UnboundedReceiverStream::new(rx), cancels);
+
+        Ok((
+            WatchStream::new(stream),
+            WatchCanceler::new(watch_id, cancel_tx),
+        ))
+    }
+}
+
+#[async_trait]
+impl LeaseOp for FakeClient {
+    async fn grant_lease<R>(&self, req: R) -> Result<LeaseGrantResponse>
+    where
+        R: Into<LeaseGrantRequest> + Send,
+    {
+        let resp = self.store().lease_grant(req.into().into())?;
+        Ok(resp.into())
+    }
+
+    async fn revoke<R>(&self, req: R) -> Result<LeaseRevokeResponse>
+    where
+        R: Into<LeaseRevokeRequest> + Send,
+    {
+        let resp = self.store().lease_revoke(req.into().into())?;
+        Ok(resp.into())
+    }
+
+    async fn keep_alive_for(&self, lease_id: LeaseId) -> Result<LeaseKeepAlive> {
+        let (req_tx, req_rx) = channel::<etcdserverpb::LeaseKeepAliveRequest>(1024);
+
+        let lease_id = self.store().lease_keep_alive(lease_id).id;
+
+        let store = Arc::clone(&self.store);
+        let resp_rx = ReceiverStream::new(req_rx).map(move |req| {
+            Ok(store
+                .lock()
+                .expect("lock fake etcd store")
+                .lease_keep_alive(req.id))
+        });
+
+        Ok(LeaseKeepAlive::new(lease_id, req_tx, resp_rx))
+    }
+
+    async fn time_to_live<R>(&self, req: R) -> Result<LeaseTimeToLiveResponse>
+    where
+        R: Into<LeaseTimeToLiveRequest> + Send,
+    {
+        let resp = self.store().lease_time_to_live(req.into().into());
+        Ok(resp.into())
+    }
+}
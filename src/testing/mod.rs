@@ -0,0 +1,12 @@
+//! In-memory etcd for testing code written against the operation traits, without an etcd cluster.
+//!
+//! Enabled by the `testing` feature.
+
+mod fake;
+#[cfg(feature = "mock-server")]
+mod server;
+mod store;
+
+pub use fake::FakeClient;
+#[cfg(feature = "mock-server")]
+pub use server::MockEtcdServer;
@@ -0,0 +1,1031 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use futures::future::{FutureExt, Shared};
+use futures::{Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+#[cfg(feature = "gzip")]
+use tonic::codec::CompressionEncoding;
+use tonic::metadata::MetadataMap;
+use tonic::transport::server::Connected;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+use super::store::{fill_txn_header, Store};
+use super::FakeClient;
+use crate::client::Endpoint;
+use crate::proto::etcdserverpb;
+use crate::proto::etcdserverpb::auth_server::{Auth, AuthServer};
+use crate::proto::etcdserverpb::cluster_server::{Cluster, ClusterServer};
+use crate::proto::etcdserverpb::kv_server::{Kv, KvServer};
+use crate::proto::etcdserverpb::lease_server::{Lease, LeaseServer};
+use crate::proto::etcdserverpb::maintenance_server::{Maintenance, MaintenanceServer};
+use crate::proto::etcdserverpb::watch_request::RequestUnion;
+use crate::proto::etcdserverpb::watch_server::{Watch, WatchServer};
+use crate::Result;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send>>;
+
+fn no_leader() -> Status {
+    Status::unavailable("etcdserver: no leader")
+}
+
+fn unavailable() -> Status {
+    Status::unavailable("mock etcd server is unavailable")
+}
+
+/// Returns whether the transaction puts any key, in either branch or in nested transactions.
+fn has_puts(txn: &etcdserverpb::TxnRequest) -> bool {
+    use etcdserverpb::request_op::Request;
+
+    txn.success
+        .iter()
+        .chain(txn.failure.iter())
+        .any(|op| match &op.request {
+            Some(Request::RequestPut(_)) => true,
+            Some(Request::RequestTxn(txn)) => has_puts(txn),
+            _ => false,
+        })
+}
+
+fn unimplemented(method: &str) -> Status {
+    Status::unimplemented(format!("mock etcd server does not support {}", method))
+}
+
+/// MockEtcdServer serves the etcd gRPC API from an in-memory store, for testing [`Client`](crate::Client)
+/// end to end without an etcd cluster.
+///
+/// KV, Watch, Lease, Auth, Cluster and Maintenance services are served, and faults can be scripted
+/// while the server is running. The store is shared with [`MockEtcdServer::fake_client`].
+///
+/// The server shuts down and closes all connections when dropped.
+pub struct MockEtcdServer {
+    state: Arc<State>,
+    fake: FakeClient,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<std::result::Result<(), tonic::transport::Error>>,
+}
+
+impl MockEtcdServer {
+    /// Starts a server on a random port of localhost with an empty store.
+    pub async fn start() -> Result<Self> {
+        Self::bind(([127, 0, 0, 1], 0).into()).await
+    }
+
+    /// Starts a server on the specified address with an empty store.
+    pub async fn bind(addr: SocketAddr) -> Result<Self> {
+        Self::bind_with(addr, FakeClient::new()).await
+    }
+
+    /// Starts a server on the Unix socket at the specified path with an empty store.
+    #[cfg(unix)]
+    pub async fn bind_unix(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        use tokio::net::UnixListener;
+        use tokio_stream::wrappers::UnixListenerStream;
+
+        let path = path.as_ref();
+        let listener = UnixListener::bind(path)?;
+        let url = format!("unix://{}", path.display());
+
+        Ok(Self::serve(
+            Server::builder(),
+            UnixListenerStream::new(listener),
+            url,
+            FakeClient::new(),
+        ))
+    }
+
+    /// Starts another server on a random port of localhost which shares the store with this one,
+    /// like a member of the same cluster. Faults and users are not shared.
+    pub async fn replica(&self) -> Result<Self> {
+        Self::bind_with(([127, 0, 0, 1], 0).into(), self.fake.clone()).await
+    }
+
+    /// Starts another server on the specified address which shares the store with this one,
+    /// such as to restart a member which has been shut down.
+    pub async fn replica_at(&self, addr: SocketAddr) -> Result<Self> {
+        Self::bind_with(addr, self.fake.clone()).await
+    }
+
+    /// Starts a server with TLS on a random port of localhost with an empty store.
+    #[cfg(feature = "tls")]
+    pub async fn start_tls(cert: impl AsRef<[u8]>, key: impl AsRef<[u8]>) -> Result<Self> {
+        use tonic::transport::{Identity, ServerTlsConfig};
+
+        let server = Server::builder()
+            .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))?;
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let url = format!("https://{}", listener.local_addr()?);
+
+        Ok(Self::serve(
+            server,
+            TcpListenerStream::new(listener),
+            url,
+            FakeClient::new(),
+        ))
+    }
+
+    async fn bind_with(addr: SocketAddr, fake: FakeClient) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        Ok(Self::serve(
+            Server::builder(),
+            TcpListenerStream::new(listener),
+            url,
+            fake,
+        ))
+    }
+
+    fn serve<I, IO>(mut server: Server, incoming: I, url: String, fake: FakeClient) -> Self
+    where
+        I: Stream<Item = io::Result<IO>> + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+    {
+        let store = fake.shared_store();
+        let member_id = store
+            .lock()
+            .expect("lock fake etcd store")
+            .add_member(url.clone());
+        let state = Arc::new(State {
+            store,
+            faults: Mutex::new(Faults::default()),
+            auth: Mutex::new(AuthState::default()),
+            requests: Mutex::new(HashMap::new()),
+            url,
+            member_id,
+        });
+        let service = MockService {
+            state: Arc::clone(&state),
+        };
+
+        let (shutdown, closed) = oneshot::channel();
+        let closed = closed.shared();
+        let incoming = {
+            let closed = closed.clone();
+            incoming.map(move |io| io.map(|io| Conn::new(io, closed.clone())))
+        };
+
+        // gzip is accepted as well when the client is built with it
+        macro_rules! server {
+            ($server:ident) => {{
+                let server = $server::new(service.clone());
+                #[cfg(feature = "gzip")]
+                let server = server
+                    .accept_compressed(CompressionEncoding::Gzip)
+                    .send_compressed(CompressionEncoding::Gzip);
+                server
+            }};
+        }
+
+        let task = tokio::spawn(
+            server
+                .add_service(server!(KvServer))
+                .add_service(server!(WatchServer))
+                .add_service(server!(LeaseServer))
+                .add_service(server!(AuthServer))
+                .add_service(server!(ClusterServer))
+                .add_service(server!(MaintenanceServer))
+                .serve_with_incoming_shutdown(incoming, closed.map(drop)),
+        );
+
+        Self {
+            state,
+            fake,
+            shutdown: Some(shutdown),
+            task,
+        }
+    }
+
+    /// Gets the URL clients connect to.
+    pub fn url(&self) -> &str {
+        &self.state.url
+    }
+
+    /// Gets the endpoint clients connect to.
+    pub fn endpoint(&self) -> Endpoint {
+        Endpoint::new(self.state.url.clone())
+    }
+
+    /// Gets a FakeClient which operates on the store of the server directly, bypassing faults.
+    pub fn fake_client(&self) -> FakeClient {
+        self.fake.clone()
+    }
+
+    /// Delays every request by the specified duration before handling it.
+    pub fn set_delay(&self, delay: Duration) {
+        self.state.faults().delay = delay;
+    }
+
+    /// When set, all requests fail with `Unavailable`.
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.state.faults().unavailable = unavailable;
+    }
+
+    /// When set, the member has no leader, so requests fail with `etcdserver: no leader`
+    /// and status reports no leader.
+    pub fn set_no_leader(&self, no_leader: bool) {
+        self.state.faults().no_leader = no_leader;
+    }
+
+    /// When set, the member raised the NOSPACE alarm, so writes fail and status reports the alarm.
+    pub fn set_nospace(&self, nospace: bool) {
+        self.state.faults().nospace = nospace;
+    }
+
+    /// Gets the ID of the member.
+    pub fn member_id(&self) -> u64 {
+        self.state.member_id
+    }
+
+    /// Makes the member the leader of the servers sharing the store, in a new raft term.
+    pub fn elect(&self) {
+        self.state.store().elect(self.state.member_id);
+    }
+
+    /// Gets the number of requests of the gRPC method received by the server, such as `put` or `range`.
+    pub fn requests(&self, method: &str) -> usize {
+        self.state.requests().get(method).copied().unwrap_or(0)
+    }
+
+    /// When set, the member stops applying the store at the current revision for serializable reads,
+    /// like a follower lagging behind the leader.
+    pub fn set_lagging(&self, lagging: bool) {
+        let revision = lagging.then(|| self.state.store().revision());
+        self.state.faults().lagging = revision;
+    }
+
+    /// Fails the next request with the specified status. Calls queue up in order.
+    pub fn fail_next(&self, status: Status) {
+        self.state.faults().next_errors.push_back(status);
+    }
+
+    /// Compacts the store at the specified revision.
+    pub fn compact(&self, revision: i64) -> Result<()> {
+        self.state
+            .store()
+            .compact(etcdserverpb::CompactionRequest {
+                revision,
+                physical: false,
+            })?;
+        Ok(())
+    }
+
+    /// Adds a user which can authenticate with the password. Auth is enabled once a user is added.
+    pub fn add_user(&self, name: impl Into<String>, password: impl Into<String>) {
+        self.state.auth().users.insert(name.into(), password.into());
+    }
+
+    /// Invalidates all tokens issued so far, as if they were expired.
+    pub fn expire_tokens(&self) {
+        self.state.auth().tokens.clear();
+    }
+
+    /// Shuts down the server and closes all connections.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.task).await;
+    }
+}
+
+#[derive(Default)]
+struct Faults {
+    delay: Duration,
+    unavailable: bool,
+    no_leader: bool,
+    nospace: bool,
+    lagging: Option<i64>,
+    next_errors: VecDeque<Status>,
+}
+
+#[derive(Default)]
+struct AuthState {
+    users: HashMap<String, String>,
+    tokens: HashSet<String>,
+    next_token: u64,
+}
+
+struct State {
+    store: Arc<Mutex<Store>>,
+    faults: Mutex<Faults>,
+    auth: Mutex<AuthState>,
+    requests: Mutex<HashMap<&'static str, usize>>,
+    url: String,
+    member_id: u64,
+}
+
+impl State {
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().expect("lock fake etcd store")
+    }
+
+    fn faults(&self) -> MutexGuard<'_, Faults> {
+        self.faults.lock().expect("lock mock etcd faults")
+    }
+
+    fn auth(&self) -> MutexGuard<'_, AuthState> {
+        self.auth.lock().expect("lock mock etcd auth")
+    }
+
+    fn requests(&self) -> MutexGuard<'_, HashMap<&'static str, usize>> {
+        self.requests.lock().expect("lock mock etcd requests")
+    }
+
+    /// Header of the responses answered by the member itself.
+    fn header(&self, store: &Store) -> etcdserverpb::ResponseHeader {
+        etcdserverpb::ResponseHeader {
+            member_id: self.member_id,
+            ..store.header()
+        }
+    }
+
+    fn has_leader(&self) -> bool {
+        !self.faults().no_leader
+    }
+
+    fn check_space(&self) -> std::result::Result<(), Status> {
+        if self.faults().nospace {
+            Err(Status::resource_exhausted(
+                "etcdserver: mvcc: database space exceeded",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn alarms(&self) -> Vec<etcdserverpb::AlarmMember> {
+        if self.faults().nospace {
+            vec![etcdserverpb::AlarmMember {
+                member_id: self.member_id,
+                alarm: etcdserverpb::AlarmType::Nospace as i32,
+            }]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Applies the scripted faults, then checks the auth token of the request.
+    async fn check(
+        &self,
+        method: &'static str,
+        metadata: &MetadataMap,
+    ) -> std::result::Result<(), Status> {
+        self.check_faults(method, true).await?;
+        self.check_token(metadata)
+    }
+
+    async fn check_faults(
+        &self,
+        method: &'static str,
+        require_leader: bool,
+    ) -> std::result::Result<(), Status> {
+        *self.requests().entry(method).or_default() += 1;
+
+        let delay = self.faults().delay;
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        let mut faults = self.faults();
+        if let Some(status) = faults.next_errors.pop_front() {
+            return Err(status);
+        }
+        if faults.unavailable {
+            return Err(unavailable());
+        }
+        if require_leader && faults.no_leader {
+            return Err(no_leader());
+        }
+
+        Ok(())
+    }
+
+    fn check_token(&self, metadata: &MetadataMap) -> std::result::Result<(), Status> {
+        let auth = self.auth();
+        if auth.users.is_empty() {
+            return Ok(());
+        }
+
+        let token = metadata
+            .get("authorization")
+            .and_then(|token| token.to_str().ok())
+            .ok_or_else(|| Status::invalid_argument("etcdserver: user name is empty"))?;
+
+        if auth.tokens.contains(token) {
+            Ok(())
+        } else {
+            Err(Status::unauthenticated("etcdserver: invalid auth token"))
+        }
+    }
+}
+
+/// Connection which is closed along with the server.
+struct Conn<T> {
+    io: T,
+    closed: Shared<oneshot::Receiver<()>>,
+    is_closed: bool,
+}
+
+impl<T> Conn<T> {
+    fn new(io: T, closed: Shared<oneshot::Receiver<()>>) -> Self {
+        Self {
+            io,
+            closed,
+            is_closed: false,
+        }
+    }
+
+    fn poll_closed(&mut self, cx: &mut Context<'_>) -> bool {
+        if !self.is_closed && self.closed.poll_unpin(cx).is_ready() {
+            self.is_closed = true;
+        }
+        self.is_closed
+    }
+}
+
+impl<T> AsyncRead for Conn<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.poll_closed(cx) {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        Pin::new(&mut this.io).poll_read(cx, buf)
+    }
+}
+
+impl<T> AsyncWrite for Conn<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.poll_closed(cx) {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        Pin::new(&mut this.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+impl<T> Connected for Conn<T>
+where
+    T: Connected,
+{
+    type ConnectInfo = T::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.io.connect_info()
+    }
+}
+
+#[derive(Clone)]
+struct MockService {
+    state: Arc<State>,
+}
+
+#[async_trait]
+impl Kv for MockService {
+    async fn range(
+        &self,
+        req: Request<etcdserverpb::RangeRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::RangeResponse>, Status> {
+        // serializable reads are answered by the member locally, even without a leader
+        self.state
+            .check_faults("range", !req.get_ref().serializable)
+            .await?;
+        self.state.check_token(req.metadata())?;
+
+        let mut req = req.into_inner();
+        let lagging = self.state.faults().lagging.filter(|_| req.serializable);
+        let mut store = self.state.store();
+        let mut header = self.state.header(&store);
+        // a lagging member has applied the store up to the revision only
+        if let Some(revision) = lagging {
+            if req.revision == 0 || req.revision > revision {
+                req.revision = revision;
+            }
+            header.revision = revision;
+        }
+
+        let mut resp = store.range(req)?;
+        resp.header = Some(header);
+        Ok(Response::new(resp))
+    }
+
+    async fn put(
+        &self,
+        req: Request<etcdserverpb::PutRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::PutResponse>, Status> {
+        self.state.check("put", req.metadata()).await?;
+        self.state.check_space()?;
+        let mut store = self.state.store();
+        let mut resp = store.put(req.into_inner())?;
+        resp.header = Some(self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+
+    async fn delete_range(
+        &self,
+        req: Request<etcdserverpb::DeleteRangeRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::DeleteRangeResponse>, Status> {
+        self.state.check("delete_range", req.metadata()).await?;
+        let mut store = self.state.store();
+        let mut resp = store.delete_range(req.into_inner())?;
+        resp.header = Some(self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+
+    async fn txn(
+        &self,
+        req: Request<etcdserverpb::TxnRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::TxnResponse>, Status> {
+        self.state.check("txn", req.metadata()).await?;
+        let req = req.into_inner();
+        if has_puts(&req) {
+            self.state.check_space()?;
+        }
+        let mut store = self.state.store();
+        let mut resp = store.txn(req)?;
+        fill_txn_header(&mut resp, &self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+
+    async fn compact(
+        &self,
+        req: Request<etcdserverpb::CompactionRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::CompactionResponse>, Status> {
+        self.state.check("compact", req.metadata()).await?;
+        let mut store = self.state.store();
+        let mut resp = store.compact(req.into_inner())?;
+        resp.header = Some(self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+}
+
+#[async_trait]
+impl Watch for MockService {
+    type WatchStream = ResponseStream<etcdserverpb::WatchResponse>;
+
+    async fn watch(
+        &self,
+        req: Request<Streaming<etcdserverpb::WatchRequest>>,
+    ) -> std::result::Result<Response<Self::WatchStream>, Status> {
+        // like etcd, the watch requires a leader only when asked to by the metadata
+        let require_leader = req.metadata().get("hasleader").is_some_and(|v| v == "true");
+        self.state.check_faults("watch", require_leader).await?;
+        self.state.check_token(req.metadata())?;
+
+        let mut inbound = req.into_inner();
+        let (tx, rx) = unbounded_channel();
+        let state = Arc::clone(&self.state);
+
+        tokio::spawn(async move {
+            let mut watch_ids = vec![];
+
+            while let Some(Ok(req)) = inbound.next().await {
+                match req.request_union {
+                    Some(RequestUnion::CreateRequest(req)) => {
+                        // the created response goes first, events of the watcher are forwarded after it
+                        let (watch_tx, mut watch_rx) = unbounded_channel();
+                        let mut store = state.store();
+                        let watch_id = store.watch(req, watch_tx);
+                        let _ = tx.send(Ok(etcdserverpb::WatchResponse {
+                            header: Some(state.header(&store)),
+                            watch_id,
+                            created: true,
+                            ..Default::default()
+                        }));
+                        drop(store);
+                        watch_ids.push(watch_id);
+
+                        let tx = tx.clone();
+                        let member_id = state.member_id;
+                        tokio::spawn(async move {
+                            while let Some(mut resp) = watch_rx.recv().await {
+                                if let Some(header) =
+                                    resp.as_mut().ok().and_then(|resp| resp.header.as_mut())
+                                {
+                                    header.member_id = member_id;
+                                }
+                                if tx.send(resp).is_err() {
+                                    break;
+                                }
+                            }
+                        });
+                    }
+                    Some(RequestUnion::CancelRequest(req)) => {
+                        state.store().cancel_watch(req.watch_id);
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut store = state.store();
+            for watch_id in watch_ids {
+                store.cancel_watch(watch_id);
+            }
+        });
+
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+#[async_trait]
+impl Lease for MockService {
+    type LeaseKeepAliveStream = ResponseStream<etcdserverpb::LeaseKeepAliveResponse>;
+
+    async fn lease_grant(
+        &self,
+        req: Request<etcdserverpb::LeaseGrantRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::LeaseGrantResponse>, Status> {
+        self.state.check("lease_grant", req.metadata()).await?;
+        self.state.check_space()?;
+        let mut store = self.state.store();
+        let mut resp = store.lease_grant(req.into_inner())?;
+        resp.header = Some(self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+
+    async fn lease_revoke(
+        &self,
+        req: Request<etcdserverpb::LeaseRevokeRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::LeaseRevokeResponse>, Status> {
+        self.state.check("lease_revoke", req.metadata()).await?;
+        let mut store = self.state.store();
+        let mut resp = store.lease_revoke(req.into_inner())?;
+        resp.header = Some(self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+
+    async fn lease_keep_alive(
+        &self,
+        req: Request<Streaming<etcdserverpb::LeaseKeepAliveRequest>>,
+    ) -> std::result::Result<Response<Self::LeaseKeepAliveStream>, Status> {
+        self.state.check("lease_keep_alive", req.metadata()).await?;
+
+        let state = Arc::clone(&self.state);
+        let stream = req.into_inner().map(move |req| {
+            req.map(|req| {
+                let mut store = state.store();
+                let mut resp = store.lease_keep_alive(req.id);
+                resp.header = Some(state.header(&store));
+                resp
+            })
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn lease_time_to_live(
+        &self,
+        req: Request<etcdserverpb::LeaseTimeToLiveRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::LeaseTimeToLiveResponse>, Status> {
+        self.state
+            .check("lease_time_to_live", req.metadata())
+            .await?;
+        let mut store = self.state.store();
+        let mut resp = store.lease_time_to_live(req.into_inner());
+        resp.header = Some(self.state.header(&store));
+        Ok(Response::new(resp))
+    }
+
+    async fn lease_leases(
+        &self,
+        req: Request<etcdserverpb::LeaseLeasesRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::LeaseLeasesResponse>, Status> {
+        self.state.check("lease_leases", req.metadata()).await?;
+        let store = self.state.store();
+        Ok(Response::new(etcdserverpb::LeaseLeasesResponse {
+            header: Some(self.state.header(&store)),
+            leases: store
+                .lease_ids()
+                .into_iter()
+                .map(|id| etcdserverpb::LeaseStatus { id })
+                .collect(),
+        }))
+    }
+}
+
+#[async_trait]
+impl Cluster for MockService {
+    async fn member_list(
+        &self,
+        req: Request<etcdserverpb::MemberListRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::MemberListResponse>, Status> {
+        self.state.check("member_list", req.metadata()).await?;
+        let store = self.state.store();
+        Ok(Response::new(etcdserverpb::MemberListResponse {
+            header: Some(self.state.header(&store)),
+            members: store
+                .members()
+                .map(|(id, url)| etcdserverpb::Member {
+                    id,
+                    name: format!("mock-{}", id),
+                    peer_ur_ls: vec![],
+                    client_ur_ls: vec![url.to_owned()],
+                    is_learner: false,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn member_add(
+        &self,
+        _: Request<etcdserverpb::MemberAddRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::MemberAddResponse>, Status> {
+        Err(unimplemented("member_add"))
+    }
+
+    async fn member_remove(
+        &self,
+        _: Request<etcdserverpb::MemberRemoveRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::MemberRemoveResponse>, Status> {
+        Err(unimplemented("member_remove"))
+    }
+
+    async fn member_update(
+        &self,
+        _: Request<etcdserverpb::MemberUpdateRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::MemberUpdateResponse>, Status> {
+        Err(unimplemented("member_update"))
+    }
+
+    async fn member_promote(
+        &self,
+        _: Request<etcdserverpb::MemberPromoteRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::MemberPromoteResponse>, Status> {
+        Err(unimplemented("member_promote"))
+    }
+}
+
+#[async_trait]
+impl Maintenance for MockService {
+    type SnapshotStream = ResponseStream<etcdserverpb::SnapshotResponse>;
+
+    async fn alarm(
+        &self,
+        req: Request<etcdserverpb::AlarmRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AlarmResponse>, Status> {
+        self.state.check("alarm", req.metadata()).await?;
+        Ok(Response::new(etcdserverpb::AlarmResponse {
+            header: Some(self.state.header(&self.state.store())),
+            alarms: self.state.alarms(),
+        }))
+    }
+
+    async fn status(
+        &self,
+        req: Request<etcdserverpb::StatusRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::StatusResponse>, Status> {
+        // status is answered by the member itself, even without a leader
+        self.state.check_faults("status", false).await?;
+        self.state.check_token(req.metadata())?;
+
+        let store = self.state.store();
+        Ok(Response::new(etcdserverpb::StatusResponse {
+            header: Some(self.state.header(&store)),
+            version: "3.5.0".to_owned(),
+            leader: if self.state.has_leader() {
+                store.leader()
+            } else {
+                0
+            },
+            raft_index: store.revision() as u64,
+            raft_term: store.raft_term(),
+            raft_applied_index: store.revision() as u64,
+            errors: self
+                .state
+                .alarms()
+                .iter()
+                .map(|alarm| format!("memberID:{} alarm:NOSPACE ", alarm.member_id))
+                .collect(),
+            ..Default::default()
+        }))
+    }
+
+    async fn defragment(
+        &self,
+        req: Request<etcdserverpb::DefragmentRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::DefragmentResponse>, Status> {
+        self.state.check("defragment", req.metadata()).await?;
+        Ok(Response::new(etcdserverpb::DefragmentResponse {
+            header: Some(self.state.header(&self.state.store())),
+        }))
+    }
+
+    async fn snapshot(
+        &self,
+        _: Request<etcdserverpb::SnapshotRequest>,
+    ) -> std::result::Result<Response<Self::SnapshotStream>, Status> {
+        Err(unimplemented("snapshot"))
+    }
+
+    async fn hash(
+        &self,
+        _: Request<etcdserverpb::HashRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::HashResponse>, Status> {
+        Err(unimplemented("hash"))
+    }
+
+    async fn hash_kv(
+        &self,
+        _: Request<etcdserverpb::HashKvRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::HashKvResponse>, Status> {
+        Err(unimplemented("hash_kv"))
+    }
+
+    async fn move_leader(
+        &self,
+        _: Request<etcdserverpb::MoveLeaderRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::MoveLeaderResponse>, Status> {
+        Err(unimplemented("move_leader"))
+    }
+
+    async fn downgrade(
+        &self,
+        _: Request<etcdserverpb::DowngradeRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::DowngradeResponse>, Status> {
+        Err(unimplemented("downgrade"))
+    }
+}
+
+#[async_trait]
+impl Auth for MockService {
+    async fn authenticate(
+        &self,
+        req: Request<etcdserverpb::AuthenticateRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthenticateResponse>, Status> {
+        self.state.check_faults("authenticate", true).await?;
+
+        let req = req.into_inner();
+        let mut auth = self.state.auth();
+        if auth.users.is_empty() {
+            return Err(Status::failed_precondition(
+                "etcdserver: authentication is not enabled",
+            ));
+        }
+        if auth.users.get(&req.name) != Some(&req.password) {
+            return Err(Status::invalid_argument(
+                "etcdserver: authentication failed, invalid user ID or password",
+            ));
+        }
+
+        auth.next_token += 1;
+        let token = format!("mock.{}.{}", req.name, auth.next_token);
+        auth.tokens.insert(token.clone());
+
+        Ok(Response::new(etcdserverpb::AuthenticateResponse {
+            header: Some(self.state.header(&self.state.store())),
+            token,
+        }))
+    }
+
+    async fn auth_status(
+        &self,
+        req: Request<etcdserverpb::AuthStatusRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthStatusResponse>, Status> {
+        self.state.check("auth_status", req.metadata()).await?;
+        let enabled = !self.state.auth().users.is_empty();
+        Ok(Response::new(etcdserverpb::AuthStatusResponse {
+            header: Some(self.state.header(&self.state.store())),
+            enabled,
+            auth_revision: 1,
+        }))
+    }
+
+    async fn auth_enable(
+        &self,
+        _: Request<etcdserverpb::AuthEnableRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthEnableResponse>, Status> {
+        Err(unimplemented("auth_enable"))
+    }
+
+    async fn auth_disable(
+        &self,
+        _: Request<etcdserverpb::AuthDisableRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthDisableResponse>, Status> {
+        Err(unimplemented("auth_disable"))
+    }
+
+    async fn user_add(
+        &self,
+        _: Request<etcdserverpb::AuthUserAddRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserAddResponse>, Status> {
+        Err(unimplemented("user_add"))
+    }
+
+    async fn user_get(
+        &self,
+        _: Request<etcdserverpb::AuthUserGetRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserGetResponse>, Status> {
+        Err(unimplemented("user_get"))
+    }
+
+    async fn user_list(
+        &self,
+        _: Request<etcdserverpb::AuthUserListRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserListResponse>, Status> {
+        Err(unimplemented("user_list"))
+    }
+
+    async fn user_delete(
+        &self,
+        _: Request<etcdserverpb::AuthUserDeleteRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserDeleteResponse>, Status> {
+        Err(unimplemented("user_delete"))
+    }
+
+    async fn user_change_password(
+        &self,
+        _: Request<etcdserverpb::AuthUserChangePasswordRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserChangePasswordResponse>, Status> {
+        Err(unimplemented("user_change_password"))
+    }
+
+    async fn user_grant_role(
+        &self,
+        _: Request<etcdserverpb::AuthUserGrantRoleRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserGrantRoleResponse>, Status> {
+        Err(unimplemented("user_grant_role"))
+    }
+
+    async fn user_revoke_role(
+        &self,
+        _: Request<etcdserverpb::AuthUserRevokeRoleRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthUserRevokeRoleResponse>, Status> {
+        Err(unimplemented("user_revoke_role"))
+    }
+
+    async fn role_add(
+        &self,
+        _: Request<etcdserverpb::AuthRoleAddRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthRoleAddResponse>, Status> {
+        Err(unimplemented("role_add"))
+    }
+
+    async fn role_get(
+        &self,
+        _: Request<etcdserverpb::AuthRoleGetRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthRoleGetResponse>, Status> {
+        Err(unimplemented("role_get"))
+    }
+
+    async fn role_list(
+        &self,
+        _: Request<etcdserverpb::AuthRoleListRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthRoleListResponse>, Status> {
+        Err(unimplemented("role_list"))
+    }
+
+    async fn role_delete(
+        &self,
+        _: Request<etcdserverpb::AuthRoleDeleteRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthRoleDeleteResponse>, Status> {
+        Err(unimplemented("role_delete"))
+    }
+
+    async fn role_grant_permission(
+        &self,
+        _: Request<etcdserverpb::AuthRoleGrantPermissionRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthRoleGrantPermissionResponse>, Status> {
+        Err(unimplemented("role_grant_permission"))
+    }
+
+    async fn role_revoke_permission(
+        &self,
+        _: Request<etcdserverpb::AuthRoleRevokePermissionRequest>,
+    ) -> std::result::Result<Response<etcdserverpb::AuthRoleRevokePermissionResponse>, Status> {
+        Err(unimplemented("role_revoke_permission"))
+    }
+}
@@ -0,0 +1,839 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
+use std::time::Duration;
+
+use tokio::sync::mpsc::UnboundedSender;
+use tonic::Status;
+
+use crate::proto::etcdserverpb::compare::{CompareResult, TargetUnion};
+use crate::proto::etcdserverpb::range_request::{SortOrder, SortTarget};
+use crate::proto::etcdserverpb::request_op::Request;
+use crate::proto::etcdserverpb::response_op::Response;
+use crate::proto::etcdserverpb::watch_create_request::FilterType;
+use crate::proto::{etcdserverpb, mvccpb};
+
+const CLUSTER_ID: u64 = 0x1000;
+pub(crate) const MEMBER_ID: u64 = 0x1;
+pub(crate) const RAFT_TERM: u64 = 1;
+
+pub(crate) type WatchSender = UnboundedSender<Result<etcdserverpb::WatchResponse, Status>>;
+
+fn key_not_provided() -> Status {
+    Status::invalid_argument("etcdserver: key is not provided")
+}
+
+fn key_not_found() -> Status {
+    Status::invalid_argument("etcdserver: key not found")
+}
+
+fn duplicate_key() -> Status {
+    Status::invalid_argument("etcdserver: duplicate key given in txn request")
+}
+
+fn lease_not_found() -> Status {
+    Status::not_found("etcdserver: requested lease not found")
+}
+
+fn lease_exists() -> Status {
+    Status::failed_precondition("etcdserver: lease already exists")
+}
+
+pub(crate) fn compacted() -> Status {
+    Status::out_of_range("etcdserver: mvcc: required revision has been compacted")
+}
+
+fn future_revision() -> Status {
+    Status::out_of_range("etcdserver: mvcc: required revision is a future revision")
+}
+
+/// Keys written by a txn, which may be written once only, like etcd, so its requests are checked against
+/// the store as it was before the txn.
+#[derive(Default)]
+struct TxnWrites<'a> {
+    puts: BTreeSet<&'a [u8]>,
+    deletes: Vec<(&'a [u8], &'a [u8])>,
+}
+
+impl<'a> TxnWrites<'a> {
+    fn put(&mut self, key: &'a [u8]) -> Result<(), Status> {
+        let deleted = self
+            .deletes
+            .iter()
+            .any(|(start, end)| in_range(key, start, end));
+        if deleted || !self.puts.insert(key) {
+            return Err(duplicate_key());
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &'a [u8], range_end: &'a [u8]) -> Result<(), Status> {
+        if self.puts.iter().any(|put| in_range(put, key, range_end)) {
+            return Err(duplicate_key());
+        }
+        self.deletes.push((key, range_end));
+        Ok(())
+    }
+}
+
+/// Checks whether the key is in the range described by etcd `key` and `range_end`.
+fn in_range(key: &[u8], start: &[u8], end: &[u8]) -> bool {
+    match end {
+        [] => key == start,
+        [0] => key >= start,
+        end => key >= start && key < end,
+    }
+}
+
+/// Revision of a key, `None` value is a tombstone.
+#[derive(Clone)]
+struct KeyRevision {
+    mod_revision: i64,
+    kv: Option<mvccpb::KeyValue>,
+}
+
+#[derive(Clone)]
+struct Lease {
+    ttl: i64,
+    expiry: Duration,
+    keys: BTreeSet<Vec<u8>>,
+}
+
+struct Watcher {
+    key: Vec<u8>,
+    range_end: Vec<u8>,
+    prev_kv: bool,
+    no_put: bool,
+    no_delete: bool,
+    tx: WatchSender,
+}
+
+impl Watcher {
+    fn filter(&self, event: &mvccpb::Event) -> Option<mvccpb::Event> {
+        let kv = event.kv.as_ref()?;
+        if !in_range(&kv.key, &self.key, &self.range_end) {
+            return None;
+        }
+
+        let is_put = event.r#type == mvccpb::event::EventType::Put as i32;
+        if (is_put && self.no_put) || (!is_put && self.no_delete) {
+            return None;
+        }
+
+        let mut event = event.clone();
+        if !self.prev_kv {
+            event.prev_kv = None;
+        }
+        Some(event)
+    }
+}
+
+/// In-memory multi-version key-value store with etcd semantics.
+pub(crate) struct Store {
+    revision: i64,
+    compact_revision: i64,
+    keys: BTreeMap<Vec<u8>, Vec<KeyRevision>>,
+    history: Vec<(i64, mvccpb::Event)>,
+    pending: Vec<mvccpb::Event>,
+    leases: HashMap<i64, Lease>,
+    next_lease_id: i64,
+    watchers: HashMap<i64, Watcher>,
+    next_watch_id: i64,
+    now: Duration,
+    // client URLs of the members serving the store, by member ID
+    members: BTreeMap<u64, String>,
+    leader: u64,
+    raft_term: u64,
+}
+
+impl Store {
+    pub(crate) fn new() -> Self {
+        Self {
+            revision: 1,
+            compact_revision: 0,
+            keys: BTreeMap::new(),
+            history: vec![],
+            pending: vec![],
+            leases: HashMap::new(),
+            next_lease_id: 0x10000,
+            watchers: HashMap::new(),
+            next_watch_id: 0,
+            now: Duration::ZERO,
+            members: BTreeMap::new(),
+            leader: MEMBER_ID,
+            raft_term: RAFT_TERM,
+        }
+    }
+
+    /// Adds a member serving the store, the first one is the leader.
+    pub(crate) fn add_member(&mut self, client_url: String) -> u64 {
+        let id = MEMBER_ID + self.members.len() as u64;
+        self.members.insert(id, client_url);
+        id
+    }
+
+    pub(crate) fn members(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.members.iter().map(|(id, url)| (*id, url.as_str()))
+    }
+
+    pub(crate) fn leader(&self) -> u64 {
+        self.leader
+    }
+
+    pub(crate) fn raft_term(&self) -> u64 {
+        self.raft_term
+    }
+
+    /// Makes the member the leader in a new term.
+    pub(crate) fn elect(&mut self, member_id: u64) {
+        self.leader = member_id;
+        self.raft_term += 1;
+    }
+
+    pub(crate) fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    pub(crate) fn compact_revision(&self) -> i64 {
+        self.compact_revision
+    }
+
+    pub(crate) fn header(&self) -> etcdserverpb::ResponseHeader {
+        etcdserverpb::ResponseHeader {
+            cluster_id: CLUSTER_ID,
+            member_id: MEMBER_ID,
+            revision: self.revision,
+            raft_term: self.raft_term,
+        }
+    }
+
+    /// Gets the key-values in the range at the revision, `None` for the latest one.
+    fn kvs_at(&self, key: &[u8], range_end: &[u8], revision: Option<i64>) -> Vec<mvccpb::KeyValue> {
+        let bounds = match range_end {
+            [] => (Bound::Included(key.to_vec()), Bound::Included(key.to_vec())),
+            [0] => (Bound::Included(key.to_vec()), Bound::Unbounded),
+            end if key < end => (Bound::Included(key.to_vec()), Bound::Excluded(end.to_vec())),
+            _ => return vec![],
+        };
+
+        self.keys
+            .range(bounds)
+            .filter_map(|(_, revs)| {
+                let rev = match revision {
+                    Some(revision) => revs.iter().rev().find(|r| r.mod_revision <= revision),
+                    None => revs.last(),
+                };
+                rev.and_then(|r| r.kv.clone())
+            })
+            .collect()
+    }
+
+    fn current(&self, key: &[u8]) -> Option<mvccpb::KeyValue> {
+        self.keys
+            .get(key)
+            .and_then(|revs| revs.last())
+            .and_then(|r| r.kv.clone())
+    }
+
+    pub(crate) fn range(
+        &mut self,
+        req: etcdserverpb::RangeRequest,
+    ) -> Result<etcdserverpb::RangeResponse, Status> {
+        let mut resp = self.apply_range(req)?;
+        resp.header = Some(self.header());
+        Ok(resp)
+    }
+
+    /// Checks the range, and gets the revision to read at, `None` for the latest one.
+    fn check_range(&self, req: &etcdserverpb::RangeRequest) -> Result<Option<i64>, Status> {
+        if req.key.is_empty() {
+            return Err(key_not_provided());
+        }
+        if req.revision <= 0 {
+            return Ok(None);
+        }
+        if req.revision < self.compact_revision {
+            return Err(compacted());
+        }
+        if req.revision > self.revision {
+            return Err(future_revision());
+        }
+        Ok(Some(req.revision))
+    }
+
+    fn apply_range(
+        &self,
+        req: etcdserverpb::RangeRequest,
+    ) -> Result<etcdserverpb::RangeResponse, Status> {
+        let revision = self.check_range(&req)?;
+        let mut kvs = self.kvs_at(&req.key, &req.range_end, revision);
+        let count = kvs.len() as i64;
+
+        kvs.retain(|kv| {
+            (req.min_mod_revision == 0 || kv.mod_revision >= req.min_mod_revision)
+                && (req.max_mod_revision == 0 || kv.mod_revision <= req.max_mod_revision)
+                && (req.min_create_revision == 0 || kv.create_revision >= req.min_create_revision)
+                && (req.max_create_revision == 0 || kv.create_revision <= req.max_create_revision)
+        });
+
+        let order = SortOrder::from_i32(req.sort_order).unwrap_or(SortOrder::None);
+        let target = SortTarget::from_i32(req.sort_target).unwrap_or(SortTarget::Key);
+        let order = match (order, target) {
+            (SortOrder::None, SortTarget::Key) => SortOrder::None,
+            (SortOrder::None, _) => SortOrder::Ascend,
+            (order, _) => order,
+        };
+        if order != SortOrder::None {
+            let cmp = |a: &mvccpb::KeyValue, b: &mvccpb::KeyValue| match target {
+                SortTarget::Key => a.key.cmp(&b.key),
+                SortTarget::Version => a.version.cmp(&b.version),
+                SortTarget::Create => a.create_revision.cmp(&b.create_revision),
+                SortTarget::Mod => a.mod_revision.cmp(&b.mod_revision),
+                SortTarget::Value => a.value.cmp(&b.value),
+            };
+            match order {
+                SortOrder::Descend => kvs.sort_by(|a, b| cmp(b, a)),
+                _ => kvs.sort_by(cmp),
+            }
+        }
+
+        let more = req.limit > 0 && kvs.len() as i64 > req.limit;
+        if more {
+            kvs.truncate(req.limit as usize);
+        }
+
+        if req.count_only {
+            kvs.clear();
+        } else if req.keys_only {
+            kvs.iter_mut().for_each(|kv| kv.value.clear());
+        }
+
+        Ok(etcdserverpb::RangeResponse {
+            header: None,
+            kvs,
+            more,
+            count,
+        })
+    }
+
+    pub(crate) fn put(
+        &mut self,
+        req: etcdserverpb::PutRequest,
+    ) -> Result<etcdserverpb::PutResponse, Status> {
+        let mut resp = self.apply_put(req)?;
+        self.commit();
+        resp.header = Some(self.header());
+        Ok(resp)
+    }
+
+    fn check_put(&self, req: &etcdserverpb::PutRequest) -> Result<(), Status> {
+        if req.key.is_empty() {
+            return Err(key_not_provided());
+        }
+
+        let lease = match self.current(&req.key) {
+            Some(prev) if req.ignore_lease => prev.lease,
+            None if req.ignore_value || req.ignore_lease => return Err(key_not_found()),
+            _ => req.lease,
+        };
+        if lease != 0 && !self.leases.contains_key(&lease) {
+            return Err(lease_not_found());
+        }
+        Ok(())
+    }
+
+    fn apply_put(
+        &mut self,
+        req: etcdserverpb::PutRequest,
+    ) -> Result<etcdserverpb::PutResponse, Status> {
+        self.check_put(&req)?;
+
+        let revision = self.revision + 1;
+        let prev = self.current(&req.key);
+
+        let (value, lease) = match &prev {
+            Some(prev) => (
+                if req.ignore_value {
+                    prev.value.clone()
+                } else {
+                    req.value
+                },
+                if req.ignore_lease {
+                    prev.lease
+                } else {
+                    req.lease
+                },
+            ),
+            None => (req.value, req.lease),
+        };
+
+        if let Some(prev) = prev.as_ref().filter(|prev| prev.lease != lease) {
+            if let Some(l) = self.leases.get_mut(&prev.lease) {
+                l.keys.remove(&req.key);
+            }
+        }
+        if let Some(l) = self.leases.get_mut(&lease) {
+            l.keys.insert(req.key.clone());
+        }
+
+        let kv = mvccpb::KeyValue {
+            key: req.key.clone(),
+            create_revision: prev.as_ref().map_or(revision, |prev| prev.create_revision),
+            mod_revision: revision,
+            version: prev.as_ref().map_or(1, |prev| prev.version + 1),
+            value,
+            lease,
+        };
+
+        self.keys.entry(req.key).or_default().push(KeyRevision {
+            mod_revision: revision,
+            kv: Some(kv.clone()),
+        });
+        self.pending.push(mvccpb::Event {
+            r#type: mvccpb::event::EventType::Put as i32,
+            kv: Some(kv),
+            prev_kv: prev.clone(),
+        });
+
+        Ok(etcdserverpb::PutResponse {
+            header: None,
+            prev_kv: prev.filter(|_| req.prev_kv),
+        })
+    }
+
+    pub(crate) fn delete_range(
+        &mut self,
+        req: etcdserverpb::DeleteRangeRequest,
+    ) -> Result<etcdserverpb::DeleteRangeResponse, Status> {
+        let mut resp = self.apply_delete_range(req)?;
+        self.commit();
+        resp.header = Some(self.header());
+        Ok(resp)
+    }
+
+    fn apply_delete_range(
+        &mut self,
+        req: etcdserverpb::DeleteRangeRequest,
+    ) -> Result<etcdserverpb::DeleteRangeResponse, Status> {
+        if req.key.is_empty() {
+            return Err(key_not_provided());
+        }
+
+        let revision = self.revision + 1;
+        let prev_kvs = self.kvs_at(&req.key, &req.range_end, None);
+
+        for prev in prev_kvs.iter() {
+            if let Some(l) = self.leases.get_mut(&prev.lease) {
+                l.keys.remove(&prev.key);
+            }
+
+            self.keys
+                .entry(prev.key.clone())
+                .or_default()
+                .push(KeyRevision {
+                    mod_revision: revision,
+                    kv: None,
+                });
+            self.pending.push(mvccpb::Event {
+                r#type: mvccpb::event::EventType::Delete as i32,
+                kv: Some(mvccpb::KeyValue {
+                    key: prev.key.clone(),
+                    mod_revision: revision,
+                    ..Default::default()
+                }),
+                prev_kv: Some(prev.clone()),
+            });
+        }
+
+        Ok(etcdserverpb::DeleteRangeResponse {
+            header: None,
+            deleted: prev_kvs.len() as i64,
+            prev_kvs: if req.prev_kv { prev_kvs } else { vec![] },
+        })
+    }
+
+    /// Applies the txn, whose compares are evaluated and requests are checked before anything is written
+    /// like etcd, so a failed txn leaves the store as it was.
+    pub(crate) fn txn(
+        &mut self,
+        req: etcdserverpb::TxnRequest,
+    ) -> Result<etcdserverpb::TxnResponse, Status> {
+        let mut path = vec![];
+        self.check_txn(&req, &mut path, &mut TxnWrites::default())?;
+
+        let mut resp = self.apply_txn(req, &mut path.into_iter())?;
+        self.commit();
+        fill_txn_header(&mut resp, &self.header());
+        Ok(resp)
+    }
+
+    /// Checks the requests of the txn and its nested txns, and records whether each one succeeded.
+    fn check_txn<'a>(
+        &self,
+        req: &'a etcdserverpb::TxnRequest,
+        path: &mut Vec<bool>,
+        writes: &mut TxnWrites<'a>,
+    ) -> Result<(), Status> {
+        for ops in [&req.success, &req.failure] {
+            let mut keys = BTreeSet::new();
+            for op in ops {
+                if let Some(Request::RequestPut(put)) = &op.request {
+                    if !keys.insert(&put.key) {
+                        return Err(duplicate_key());
+                    }
+                }
+            }
+        }
+
+        let succeeded = req.compare.iter().all(|cmp| self.compare(cmp));
+        path.push(succeeded);
+
+        for op in if succeeded {
+            &req.success
+        } else {
+            &req.failure
+        } {
+            match &op.request {
+                Some(Request::RequestRange(req)) => {
+                    self.check_range(req)?;
+                }
+                Some(Request::RequestPut(req)) => {
+                    self.check_put(req)?;
+                    writes.put(&req.key)?;
+                }
+                Some(Request::RequestDeleteRange(req)) => {
+                    if req.key.is_empty() {
+                        return Err(key_not_provided());
+                    }
+                    writes.delete(&req.key, &req.range_end)?;
+                }
+                Some(Request::RequestTxn(req)) => self.check_txn(req, path, writes)?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the checked txn, taking whether each txn succeeded from the path.
+    fn apply_txn(
+        &mut self,
+        req: etcdserverpb::TxnRequest,
+        path: &mut impl Iterator<Item = bool>,
+    ) -> Result<etcdserverpb::TxnResponse, Status> {
+        let succeeded = path.next().unwrap_or_default();
+        let ops = if succeeded { req.success } else { req.failure };
+
+        let mut responses = Vec::with_capacity(ops.len());
+        for op in ops {
+            let resp = match op.request {
+                Some(Request::RequestRange(req)) => Response::ResponseRange(self.apply_range(req)?),
+                Some(Request::RequestPut(req)) => Response::ResponsePut(self.apply_put(req)?),
+                Some(Request::RequestDeleteRange(req)) => {
+                    Response::ResponseDeleteRange(self.apply_delete_range(req)?)
+                }
+                Some(Request::RequestTxn(req)) => Response::ResponseTxn(self.apply_txn(req, path)?),
+                None => continue,
+            };
+            responses.push(etcdserverpb::ResponseOp {
+                response: Some(resp),
+            });
+        }
+
+        Ok(etcdserverpb::TxnResponse {
+            header: None,
+            succeeded,
+            responses,
+        })
+    }
+
+    fn compare(&self, cmp: &etcdserverpb::Compare) -> bool {
+        let kvs = self.kvs_at(&cmp.key, &cmp.range_end, None);
+        if kvs.is_empty() {
+            if let Some(TargetUnion::Value(_)) = cmp.target_union {
+                return false;
+            }
+            return compare_kv(cmp, &mvccpb::KeyValue::default());
+        }
+        kvs.iter().all(|kv| compare_kv(cmp, kv))
+    }
+
+    pub(crate) fn compact(
+        &mut self,
+        req: etcdserverpb::CompactionRequest,
+    ) -> Result<etcdserverpb::CompactionResponse, Status> {
+        let revision = req.revision;
+        if revision <= self.compact_revision {
+            return Err(compacted());
+        }
+        if revision > self.revision {
+            return Err(future_revision());
+        }
+
+        self.compact_revision = revision;
+
+        for revs in self.keys.values_mut() {
+            if let Some(i) = revs.iter().rposition(|r| r.mod_revision <= revision) {
+                revs.drain(..i);
+                if revs[0].kv.is_none() {
+                    revs.remove(0);
+                }
+            }
+        }
+        self.keys.retain(|_, revs| !revs.is_empty());
+        self.history.retain(|(rev, _)| *rev >= revision);
+
+        Ok(etcdserverpb::CompactionResponse {
+            header: Some(self.header()),
+        })
+    }
+
+    /// Applies pending changes as a new revision and notifies watchers.
+    fn commit(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        self.revision += 1;
+        let events = std::mem::take(&mut self.pending);
+
+        let header = self.header();
+        self.watchers.retain(|watch_id, watcher| {
+            let events: Vec<_> = events.iter().filter_map(|e| watcher.filter(e)).collect();
+            if events.is_empty() {
+                return true;
+            }
+
+            watcher
+                .tx
+                .send(Ok(etcdserverpb::WatchResponse {
+                    header: Some(header.clone()),
+                    watch_id: *watch_id,
+                    events,
+                    ..Default::default()
+                }))
+                .is_ok()
+        });
+
+        let revision = self.revision;
+        self.history
+            .extend(events.into_iter().map(|event| (revision, event)));
+    }
+
+    /// Registers a watcher and returns its ID.
+    /// Events since the start revision are sent at once.
+    pub(crate) fn watch(&mut self, req: etcdserverpb::WatchCreateRequest, tx: WatchSender) -> i64 {
+        let watch_id = if req.watch_id != 0 {
+            req.watch_id
+        } else {
+            self.next_watch_id += 1;
+            self.next_watch_id
+        };
+
+        if req.start_revision > 0 && req.start_revision < self.compact_revision {
+            let _ = tx.send(Ok(etcdserverpb::WatchResponse {
+                header: Some(self.header()),
+                watch_id,
+                canceled: true,
+                compact_revision: self.compact_revision,
+                cancel_reason: compacted().message().to_owned(),
+                ..Default::default()
+            }));
+            return watch_id;
+        }
+
+        let watcher = Watcher {
+            key: req.key,
+            range_end: req.range_end,
+            prev_kv: req.prev_kv,
+            no_put: req.filters.contains(&(FilterType::Noput as i32)),
+            no_delete: req.filters.contains(&(FilterType::Nodelete as i32)),
+            tx,
+        };
+
+        if req.start_revision > 0 {
+            let events: Vec<_> = self
+                .history
+                .iter()
+                .filter(|(rev, _)| *rev >= req.start_revision)
+                .filter_map(|(_, e)| watcher.filter(e))
+                .collect();
+            if !events.is_empty() {
+                let _ = watcher.tx.send(Ok(etcdserverpb::WatchResponse {
+                    header: Some(self.header()),
+                    watch_id,
+                    events,
+                    ..Default::default()
+                }));
+            }
+        }
+
+        self.watchers.insert(watch_id, watcher);
+        watch_id
+    }
+
+    /// Removes the watcher, a canceled response is sent before closing.
+    pub(crate) fn cancel_watch(&mut self, watch_id: i64) {
+        if let Some(watcher) = self.watchers.remove(&watch_id) {
+            let _ = watcher.tx.send(Ok(etcdserverpb::WatchResponse {
+                header: Some(self.header()),
+                watch_id,
+                canceled: true,
+                ..Default::default()
+            }));
+        }
+    }
+
+    pub(crate) fn lease_grant(
+        &mut self,
+        req: etcdserverpb::LeaseGrantRequest,
+    ) -> Result<etcdserverpb::LeaseGrantResponse, Status> {
+        let id = if req.id != 0 {
+            req.id
+        } else {
+            while self.leases.contains_key(&self.next_lease_id) {
+                self.next_lease_id += 1;
+            }
+            self.next_lease_id
+        };
+
+        if self.leases.contains_key(&id) {
+            return Err(lease_exists());
+        }
+
+        self.leases.insert(
+            id,
+            Lease {
+                ttl: req.ttl,
+                expiry: self.now + Duration::from_secs(req.ttl.max(0) as u64),
+                keys: BTreeSet::new(),
+            },
+        );
+
+        Ok(etcdserverpb::LeaseGrantResponse {
+            header: Some(self.header()),
+            id,
+            ttl: req.ttl,
+            error: String::new(),
+        })
+    }
+
+    pub(crate) fn lease_revoke(
+        &mut self,
+        req: etcdserverpb::LeaseRevokeRequest,
+    ) -> Result<etcdserverpb::LeaseRevokeResponse, Status> {
+        let lease = self.leases.remove(&req.id).ok_or_else(lease_not_found)?;
+
+        for key in lease.keys {
+            // detached from the lease already, so the deletion never fails
+            let _ = self.apply_delete_range(etcdserverpb::DeleteRangeRequest {
+                key,
+                ..Default::default()
+            });
+        }
+        self.commit();
+
+        Ok(etcdserverpb::LeaseRevokeResponse {
+            header: Some(self.header()),
+        })
+    }
+
+    pub(crate) fn lease_ids(&self) -> Vec<i64> {
+        let mut ids: Vec<_> = self.leases.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub(crate) fn lease_keep_alive(&mut self, id: i64) -> etcdserverpb::LeaseKeepAliveResponse {
+        let ttl = match self.leases.get_mut(&id) {
+            Some(lease) => {
+                lease.expiry = self.now + Duration::from_secs(lease.ttl.max(0) as u64);
+                lease.ttl
+            }
+            None => 0,
+        };
+
+        etcdserverpb::LeaseKeepAliveResponse {
+            header: Some(self.header()),
+            id,
+            ttl,
+        }
+    }
+
+    pub(crate) fn lease_time_to_live(
+        &mut self,
+        req: etcdserverpb::LeaseTimeToLiveRequest,
+    ) -> etcdserverpb::LeaseTimeToLiveResponse {
+        let (ttl, granted_ttl, keys) = match self.leases.get(&req.id) {
+            Some(lease) => (
+                lease.expiry.saturating_sub(self.now).as_secs() as i64,
+                lease.ttl,
+                if req.keys {
+                    lease.keys.iter().cloned().collect()
+                } else {
+                    vec![]
+                },
+            ),
+            None => (-1, 0, vec![]),
+        };
+
+        etcdserverpb::LeaseTimeToLiveResponse {
+            header: Some(self.header()),
+            id: req.id,
+            ttl,
+            granted_ttl,
+            keys,
+        }
+    }
+
+    /// Moves the clock forward, leases whose TTL elapsed are revoked.
+    pub(crate) fn advance(&mut self, duration: Duration) {
+        self.now += duration;
+
+        let mut expired: Vec<_> = self
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.expiry <= self.now)
+            .map(|(id, _)| *id)
+            .collect();
+        expired.sort_unstable();
+
+        for id in expired {
+            let _ = self.lease_revoke(etcdserverpb::LeaseRevokeRequest { id });
+        }
+    }
+}
+
+fn compare_kv(cmp: &etcdserverpb::Compare, kv: &mvccpb::KeyValue) -> bool {
+    let ord = match &cmp.target_union {
+        Some(TargetUnion::Version(v)) => kv.version.cmp(v),
+        Some(TargetUnion::CreateRevision(v)) => kv.create_revision.cmp(v),
+        Some(TargetUnion::ModRevision(v)) => kv.mod_revision.cmp(v),
+        Some(TargetUnion::Value(v)) => kv.value.as_slice().cmp(v.as_slice()),
+        Some(TargetUnion::Lease(v)) => kv.lease.cmp(v),
+        None => return false,
+    };
+
+    match CompareResult::from_i32(cmp.result) {
+        Some(CompareResult::Equal) => ord == Ordering::Equal,
+        Some(CompareResult::Greater) => ord == Ordering::Greater,
+        Some(CompareResult::Less) => ord == Ordering::Less,
+        Some(CompareResult::NotEqual) => ord != Ordering::Equal,
+        None => false,
+    }
+}
+
+pub(crate) fn fill_txn_header(
+    resp: &mut etcdserverpb::TxnResponse,
+    header: &etcdserverpb::ResponseHeader,
+) {
+    resp.header = Some(header.clone());
+    for op in resp.responses.iter_mut() {
+        match op.response.as_mut() {
+            Some(Response::ResponseRange(r)) => r.header = Some(header.clone()),
+            Some(Response::ResponsePut(r)) => r.header = Some(header.clone()),
+            Some(Response::ResponseDeleteRange(r)) => r.header = Some(header.clone()),
+            Some(Response::ResponseTxn(r)) => fill_txn_header(r, header),
+            None => {}
+        }
+    }
+}
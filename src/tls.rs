@@ -0,0 +1,131 @@
+//! TLS certificates loaded from PEM files, which are reloaded when they change.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+use crate::balance::EndpointSet;
+use crate::client::Endpoint;
+use crate::Result;
+
+/// Paths of the PEM files to dial an endpoint with TLS.
+///
+/// The files are read when connecting, and read again periodically to pick up rotated certificates,
+/// see [`ClientConfig::tls_reload_interval`](crate::ClientConfig::tls_reload_interval).
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    domain_name: String,
+    ca_cert: Option<PathBuf>,
+    identity: Option<(PathBuf, PathBuf)>,
+}
+
+impl TlsFiles {
+    /// Verifies the server with the CA certificate, without a client certificate.
+    pub fn new(domain_name: impl Into<String>, ca_cert_path: impl Into<PathBuf>) -> Self {
+        Self {
+            domain_name: domain_name.into(),
+            ca_cert: Some(ca_cert_path.into()),
+            identity: None,
+        }
+    }
+
+    /// Verifies the server with the system root certificates, which requires the `tls-roots` feature.
+    pub fn system_roots(domain_name: impl Into<String>) -> Self {
+        Self {
+            domain_name: domain_name.into(),
+            ca_cert: None,
+            identity: None,
+        }
+    }
+
+    /// Sets the client certificate and key.
+    pub fn identity(
+        mut self,
+        client_cert_path: impl Into<PathBuf>,
+        client_key_path: impl Into<PathBuf>,
+    ) -> Self {
+        self.identity = Some((client_cert_path.into(), client_key_path.into()));
+        self
+    }
+
+    /// Reads the content of the files.
+    pub(crate) async fn read(&self) -> Result<TlsPem> {
+        use tokio::fs::read;
+
+        let ca_cert = match &self.ca_cert {
+            Some(ca_cert) => Some(read(ca_cert).await?),
+            None => None,
+        };
+        let identity = match &self.identity {
+            Some((cert, key)) => Some((read(cert).await?, read(key).await?)),
+            None => None,
+        };
+
+        Ok(TlsPem { ca_cert, identity })
+    }
+
+    pub(crate) fn config(&self, pem: &TlsPem) -> ClientTlsConfig {
+        let mut config = ClientTlsConfig::new().domain_name(self.domain_name.clone());
+        if let Some(ca_cert) = &pem.ca_cert {
+            config = config.ca_certificate(Certificate::from_pem(ca_cert));
+        }
+
+        match &pem.identity {
+            Some((cert, key)) => config.identity(Identity::from_pem(cert, key)),
+            None => config,
+        }
+    }
+}
+
+/// Content of [`TlsFiles`].
+#[derive(PartialEq, Eq)]
+pub(crate) struct TlsPem {
+    ca_cert: Option<Vec<u8>>,
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Reads the files of the endpoints periodically, and replaces the endpoints of the set
+/// whose certificates changed, until the balanced channel is dropped.
+///
+/// Files which cannot be read, such as in the middle of a rotation, keep the current certificates.
+pub(crate) fn reload<F>(
+    mut files: HashMap<String, (Endpoint, TlsFiles, TlsPem)>,
+    interval: Duration,
+    endpoints: Arc<EndpointSet>,
+    build: F,
+) where
+    F: Fn(&Endpoint) -> Result<tonic::transport::Endpoint> + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let sleep = tokio::time::sleep(interval);
+            if let Either::Left(_) =
+                future::select(Box::pin(endpoints.closed()), Box::pin(sleep)).await
+            {
+                return;
+            }
+
+            for (url, (endpoint, files, current)) in files.iter_mut() {
+                let Ok(pem) = files.read().await else {
+                    continue;
+                };
+                if pem == *current {
+                    continue;
+                }
+
+                let Ok(channel_endpoint) = build(&endpoint.clone().tls_config(files.config(&pem)))
+                else {
+                    continue;
+                };
+                if endpoints.replace(url, channel_endpoint).await.is_err() {
+                    return;
+                }
+                *current = pem;
+            }
+        }
+    });
+}
@@ -0,0 +1,194 @@
+//! Instrumentation and deadlines of client operations.
+//!
+//! Spans are emitted with the `tracing` feature, and the `opentelemetry` feature propagates
+//! the trace context to etcd server as W3C `traceparent` metadata. Metrics of the same operations
+//! are recorded with the `metrics` feature.
+
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(feature = "tracing")]
+use tracing::{field::Empty, Instrument, Span};
+
+use crate::metrics::{self, RequestMetrics};
+use crate::{Error, Result};
+
+/// Maximum number of bytes of a key recorded in spans.
+#[cfg(feature = "tracing")]
+const MAX_KEY_LEN: usize = 64;
+
+/// Span of a client operation, which also enforces the timeout of the operation.
+pub(crate) struct OpSpan {
+    op: &'static str,
+    timeout: Option<Duration>,
+    #[cfg(feature = "tracing")]
+    span: Span,
+    metrics: RequestMetrics,
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+impl OpSpan {
+    pub(crate) fn new(op: &'static str, endpoint: &str) -> Self {
+        Self {
+            op,
+            timeout: None,
+            metrics: RequestMetrics::new(op),
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "etcd",
+                otel.name = op,
+                otel.status_code = Empty,
+                op,
+                endpoint,
+                key = Empty,
+                range_end = Empty,
+                revision = Empty,
+                lease_id = Empty,
+                outcome = Empty,
+            ),
+        }
+    }
+
+    pub(crate) fn key(self, key: &[u8], range_end: &[u8]) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("key", display_key(key).as_str());
+            if !range_end.is_empty() {
+                self.span
+                    .record("range_end", display_key(range_end).as_str());
+            }
+        }
+        self
+    }
+
+    pub(crate) fn revision(self, revision: i64) -> Self {
+        #[cfg(feature = "tracing")]
+        if revision > 0 {
+            self.span.record("revision", revision);
+        }
+        self
+    }
+
+    pub(crate) fn lease_id(self, lease_id: i64) -> Self {
+        #[cfg(feature = "tracing")]
+        if lease_id != 0 {
+            self.span.record("lease_id", lease_id);
+        }
+        self
+    }
+
+    pub(crate) fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Records the request message sent by the operation.
+    pub(crate) fn request<M: prost::Message>(self, req: &M) -> Self {
+        metrics::sent(self.op, req);
+        self
+    }
+
+    /// Runs the operation of a unary RPC, the response message is recorded as well.
+    pub(crate) async fn run_unary<M, F>(self, fut: F) -> Result<M>
+    where
+        M: prost::Message,
+        F: Future<Output = Result<M>>,
+    {
+        let op = self.op;
+        self.run(async move {
+            let resp = fut.await?;
+            metrics::received(op, &resp);
+            Ok(resp)
+        })
+        .await
+    }
+
+    /// Runs the operation in the span and records its outcome.
+    pub(crate) async fn run<T, F>(self, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let timeout = self.timeout;
+        let fut = async move {
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, fut)
+                    .await
+                    .map_err(|_| Error::Timeout)?,
+                None => fut.await,
+            }
+        };
+
+        #[cfg(feature = "tracing")]
+        let result = {
+            let span = self.span.clone();
+            let result = fut.instrument(self.span).await;
+            match &result {
+                Ok(_) => {
+                    span.record("outcome", "ok");
+                }
+                Err(e) => {
+                    span.record("otel.status_code", "ERROR");
+                    span.record("outcome", outcome(e).as_str());
+                    tracing::debug!(parent: &span, error = %e, "etcd request failed");
+                }
+            }
+            result
+        };
+
+        #[cfg(not(feature = "tracing"))]
+        let result = fut.await;
+
+        self.metrics.finish(result.as_ref().map(drop));
+        result
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn display_key(key: &[u8]) -> String {
+    match key {
+        [0] => "\\0".to_owned(),
+        key if key.len() > MAX_KEY_LEN => {
+            format!("{}...", String::from_utf8_lossy(&key[..MAX_KEY_LEN]))
+        }
+        key => String::from_utf8_lossy(key).into_owned(),
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn outcome(e: &crate::Error) -> String {
+    match e {
+        crate::Error::Response(status) => format!("{:?}", status.code()),
+        e => e.to_string(),
+    }
+}
+
+/// Injects the W3C trace context of the current span into the request metadata.
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn inject_context(metadata: &mut tonic::metadata::MetadataMap) {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let cx = Span::current().context();
+    let span = cx.span();
+    let span_cx = span.span_context();
+    if !span_cx.is_valid() {
+        return;
+    }
+
+    let traceparent = format!(
+        "00-{:032x}-{:016x}-{:02x}",
+        span_cx.trace_id(),
+        span_cx.span_id(),
+        span_cx.trace_flags().to_u8()
+    );
+    if let Ok(value) = traceparent.parse() {
+        metadata.insert("traceparent", value);
+    }
+
+    let tracestate = span_cx.trace_state().header();
+    if !tracestate.is_empty() {
+        if let Ok(value) = tracestate.parse() {
+            metadata.insert("tracestate", value);
+        }
+    }
+}
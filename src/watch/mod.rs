@@ -8,9 +8,10 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use async_trait::async_trait;
-use futures::Stream;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
 use tokio::sync::mpsc::Sender;
-use tonic::Streaming;
+use tonic::Status;
 
 use crate::proto::etcdserverpb;
 use crate::proto::mvccpb;
@@ -35,25 +36,39 @@ pub enum WatchInbound {
 }
 
 pub struct WatchStream {
-    stream: Streaming<etcdserverpb::WatchResponse>,
+    stream: BoxStream<'static, std::result::Result<etcdserverpb::WatchResponse, Status>>,
     is_closed: bool,
 }
 
 impl WatchStream {
-    pub(crate) fn new(stream: Streaming<etcdserverpb::WatchResponse>) -> Self {
+    pub(crate) fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = std::result::Result<etcdserverpb::WatchResponse, Status>> + Send + 'static,
+    {
         Self {
-            stream,
+            stream: stream.boxed(),
             is_closed: false,
         }
     }
 
+    /// Rewrites every response received from the underlying stream.
+    pub(crate) fn map_response<F>(self, mut f: F) -> Self
+    where
+        F: FnMut(etcdserverpb::WatchResponse) -> etcdserverpb::WatchResponse + Send + 'static,
+    {
+        Self {
+            stream: self.stream.map(move |resp| resp.map(&mut f)).boxed(),
+            is_closed: self.is_closed,
+        }
+    }
+
     pub async fn inbound(&mut self) -> WatchInbound {
         if self.is_closed {
             return WatchInbound::Closed;
         }
 
-        match self.stream.message().await {
-            Ok(Some(resp)) => {
+        match self.stream.next().await {
+            Some(Ok(resp)) => {
                 if resp.canceled {
                     self.is_closed = true;
                 }
@@ -64,8 +79,8 @@ impl WatchStream {
                     WatchInbound::Ready(resp.into())
                 }
             }
-            Ok(None) => WatchInbound::Interrupted(Error::WatchEventExhausted),
-            Err(e) => WatchInbound::Interrupted(e.into()),
+            None => WatchInbound::Interrupted(Error::WatchEventExhausted),
+            Some(Err(e)) => WatchInbound::Interrupted(e.into()),
         }
     }
 }
@@ -74,13 +89,11 @@ impl Stream for WatchStream {
     type Item = WatchInbound;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().stream)
-            .poll_next(cx)
-            .map(|e| match e {
-                Some(Ok(resp)) => Some(WatchInbound::Ready(resp.into())),
-                Some(Err(e)) => Some(WatchInbound::Interrupted(e.into())),
-                None => Some(WatchInbound::Closed),
-            })
+        self.get_mut().stream.poll_next_unpin(cx).map(|e| match e {
+            Some(Ok(resp)) => Some(WatchInbound::Ready(resp.into())),
+            Some(Err(e)) => Some(WatchInbound::Interrupted(e.into())),
+            None => Some(WatchInbound::Closed),
+        })
     }
 }
 
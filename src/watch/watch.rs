@@ -48,6 +48,12 @@ impl From<WatchCreateRequest> for etcdserverpb::WatchCreateRequest {
     }
 }
 
+impl From<etcdserverpb::WatchCreateRequest> for WatchCreateRequest {
+    fn from(proto: etcdserverpb::WatchCreateRequest) -> Self {
+        Self { proto }
+    }
+}
+
 impl From<WatchCreateRequest> for etcdserverpb::WatchRequest {
     fn from(value: WatchCreateRequest) -> Self {
         etcdserverpb::WatchRequest {
@@ -102,6 +108,9 @@ pub struct WatchResponse {
     pub watch_id: i64,
     pub created: bool,
     pub canceled: bool,
+    /// The minimum revision which can be watched, set when the watch is canceled because
+    /// its start revision has been compacted.
+    pub compact_revision: i64,
     pub events: Vec<Event>,
 }
 
@@ -112,6 +121,7 @@ impl From<etcdserverpb::WatchResponse> for WatchResponse {
             watch_id: proto.watch_id,
             created: proto.created,
             canceled: proto.canceled,
+            compact_revision: proto.compact_revision,
             events: proto.events.into_iter().map(From::from).collect(),
         }
     }